@@ -0,0 +1,10 @@
+use std::env;
+use std::path::Path;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    // The committed copy in include/ is checked against this one by the header test
+    cbindgen::generate(&crate_dir).expect("Unable to generate C header").write_to_file(Path::new(&out_dir).join("sg_image_reader.h"));
+}
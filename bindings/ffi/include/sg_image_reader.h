@@ -0,0 +1,133 @@
+#ifndef SG_IMAGE_READER_H
+#define SG_IMAGE_READER_H
+
+/* Generated with cbindgen from bindings/ffi, do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of a call to one of the functions.
+ */
+typedef enum SgErrorCode {
+  SG_ERROR_CODE_OK = 0,
+  SG_ERROR_CODE_INVALID_ARGUMENT,
+  SG_ERROR_CODE_INDEX_OUT_OF_RANGE,
+  SG_ERROR_CODE_BUFFER_TOO_SMALL,
+  SG_ERROR_CODE_INVALID_HEADER,
+  SG_ERROR_CODE_IMAGE_DATA_LENGTH_MISMATCH,
+  SG_ERROR_CODE_UNKNOWN_IMAGE_TYPE,
+  SG_ERROR_CODE_UNEXPECTED_EOF,
+  SG_ERROR_CODE_IO_ERROR,
+  SG_ERROR_CODE_UTF8_ERROR,
+  SG_ERROR_CODE_OTHER,
+  SG_ERROR_CODE_UNSUPPORTED_VERSION,
+  SG_ERROR_CODE_BUILDER_ERROR,
+  /**
+   * The call panicked, which indicates a bug in the library.
+   */
+  SG_ERROR_CODE_PANIC,
+} SgErrorCode;
+
+/**
+ * Opaque handle of an opened sg file.
+ */
+typedef struct SgFile SgFile;
+
+/**
+ * Metadata of an image.
+ */
+typedef struct SgImageInfo {
+  uint32_t id;
+  uint16_t width;
+  uint16_t height;
+  uint16_t image_type;
+  uint8_t bitmap_id;
+  bool is_external;
+  int32_t invert_offset;
+  uint16_t x_offset;
+  uint16_t y_offset;
+  uint16_t anim_sprites;
+  uint8_t anim_speed_id;
+  uint8_t is_reversible;
+  uint32_t offset;
+  uint32_t length;
+  uint32_t uncompressed_length;
+  uint32_t alpha_offset;
+  uint32_t alpha_length;
+} SgImageInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Get description of the last error that occurred on the calling thread.
+ *
+ * Returns null if no error occurred yet. The string is valid until the next failing call on the same thread.
+ */
+const char *sg_last_error_message(void);
+
+/**
+ * Open the sg file at the given path, loading its metadata.
+ *
+ * On success the handle is written to `out` and has to be released with [sg_file_free].
+ *
+ * # Safety
+ *
+ * `path` has to be a valid null terminated string and `out` a valid pointer.
+ */
+enum SgErrorCode sg_file_open(const char *path, struct SgFile **out);
+
+/**
+ * Release a handle obtained from [sg_file_open].
+ *
+ * # Safety
+ *
+ * `file` has to be null or a handle obtained from [sg_file_open] that was not released yet.
+ */
+void sg_file_free(struct SgFile *file);
+
+/**
+ * Get the number of images in the file.
+ *
+ * # Safety
+ *
+ * `file` has to be a valid handle.
+ */
+uintptr_t sg_file_image_count(const struct SgFile *file);
+
+/**
+ * Get metadata of the image with the given index, writing it to `out`.
+ *
+ * # Safety
+ *
+ * `file` has to be a valid handle and `out` a valid pointer.
+ */
+enum SgErrorCode sg_file_image_info(const struct SgFile *file,
+                                    uintptr_t index,
+                                    struct SgImageInfo *out);
+
+/**
+ * Decode the image with the given index into the RGBA buffer provided by the caller.
+ *
+ * Rows of the image are written `stride` bytes apart, which has to be at least `width * 4`.
+ * The buffer has to hold at least `(height - 1) * stride + width * 4` bytes.
+ *
+ * # Safety
+ *
+ * `file` has to be a valid handle and `buffer` has to point to at least `buffer_length` writable bytes.
+ */
+enum SgErrorCode sg_file_decode_image(const struct SgFile *file,
+                                      uintptr_t index,
+                                      uint8_t *buffer,
+                                      uintptr_t buffer_length,
+                                      uintptr_t stride);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SG_IMAGE_READER_H */
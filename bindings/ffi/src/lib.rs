@@ -0,0 +1,258 @@
+//! C bindings for reading sg3 files.
+//!
+//! The header is generated when building the crate, with a copy kept in `include/sg_image_reader.h`.
+//!
+//! Functions report failures by returning an [SgErrorCode], with a description of the last error available from [sg_last_error_message].
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::BufReader;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use sg_image_reader::{RegionImageBuilderFactory, SgFileMetadata, SgImageError};
+
+/// Result of a call to one of the functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SgErrorCode {
+    Ok = 0,
+    InvalidArgument,
+    IndexOutOfRange,
+    BufferTooSmall,
+    InvalidHeader,
+    ImageDataLengthMismatch,
+    UnknownImageType,
+    UnexpectedEof,
+    IoError,
+    Utf8Error,
+    Other,
+    UnsupportedVersion,
+    BuilderError,
+    /// The call panicked, which indicates a bug in the library.
+    Panic,
+}
+
+/// Opaque handle of an opened sg file.
+pub struct SgFile {
+    metadata: SgFileMetadata,
+}
+
+/// Metadata of an image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SgImageInfo {
+    pub id: u32,
+    pub width: u16,
+    pub height: u16,
+    pub image_type: u16,
+    pub bitmap_id: u8,
+    pub is_external: bool,
+    pub invert_offset: i32,
+    pub x_offset: u16,
+    pub y_offset: u16,
+    pub anim_sprites: u16,
+    pub anim_speed_id: u8,
+    pub is_reversible: u8,
+    pub offset: u32,
+    pub length: u32,
+    pub uncompressed_length: u32,
+    pub alpha_offset: u32,
+    pub alpha_length: u32,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(code: SgErrorCode, message: String) -> SgErrorCode {
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+    code
+}
+
+fn error_code(err: SgImageError) -> SgErrorCode {
+    let code = match err {
+        SgImageError::InvalidHeader => SgErrorCode::InvalidHeader,
+        SgImageError::UnsupportedVersion(_) => SgErrorCode::UnsupportedVersion,
+        SgImageError::ImageDataLengthMismatch => SgErrorCode::ImageDataLengthMismatch,
+        SgImageError::UnknownImageType(_) => SgErrorCode::UnknownImageType,
+        SgImageError::UnexpectedEof => SgErrorCode::UnexpectedEof,
+        SgImageError::InvalidImageIndex(_) | SgImageError::InvalidBitmapId(_) => SgErrorCode::IndexOutOfRange,
+        SgImageError::IoError(_) => SgErrorCode::IoError,
+        SgImageError::Utf8Error(_) => SgErrorCode::Utf8Error,
+        SgImageError::BuilderError(_) => SgErrorCode::BuilderError,
+        // Variants enabled by the `image` and `import` features of the library, which are not used by the bindings
+        #[allow(unreachable_patterns)]
+        _ => SgErrorCode::Other,
+    };
+    set_last_error(code, err.to_string())
+}
+
+/// Run the body of an exported function, turning a panic into the given value instead of unwinding into the caller.
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+/// Run the body of an exported function, turning a panic into [SgErrorCode::Panic].
+fn guard_code(f: impl FnOnce() -> SgErrorCode) -> SgErrorCode {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic.downcast_ref::<&str>().map(|message| message.to_string()).or_else(|| panic.downcast_ref::<String>().cloned());
+        set_last_error(SgErrorCode::Panic, format!("panicked: {}", message.unwrap_or_default()))
+    })
+}
+
+/// Get description of the last error that occurred on the calling thread.
+///
+/// Returns null if no error occurred yet. The string is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn sg_last_error_message() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+    })
+}
+
+/// Open the sg file at the given path, loading its metadata.
+///
+/// On success the handle is written to `out` and has to be released with [sg_file_free].
+///
+/// # Safety
+///
+/// `path` has to be a valid null terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sg_file_open(path: *const c_char, out: *mut *mut SgFile) -> SgErrorCode {
+    guard_code(|| {
+        if path.is_null() || out.is_null() {
+            return set_last_error(SgErrorCode::InvalidArgument, String::from("null pointer passed"));
+        }
+
+        let path = match CStr::from_ptr(path).to_str() {
+            Ok(path) => path,
+            Err(err) => return set_last_error(SgErrorCode::Utf8Error, err.to_string()),
+        };
+
+        match SgFileMetadata::load_metadata_from_path(path) {
+            Ok(metadata) => {
+                *out = Box::into_raw(Box::new(SgFile { metadata }));
+                SgErrorCode::Ok
+            }
+            Err(err) => error_code(err),
+        }
+    })
+}
+
+/// Release a handle obtained from [sg_file_open].
+///
+/// # Safety
+///
+/// `file` has to be null or a handle obtained from [sg_file_open] that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sg_file_free(file: *mut SgFile) {
+    guard((), || {
+        if !file.is_null() {
+            drop(Box::from_raw(file));
+        }
+    })
+}
+
+/// Get the number of images in the file.
+///
+/// # Safety
+///
+/// `file` has to be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn sg_file_image_count(file: *const SgFile) -> usize {
+    guard(0, || {
+        file.as_ref().map_or(0, |file| file.metadata.images.len())
+    })
+}
+
+/// Get metadata of the image with the given index, writing it to `out`.
+///
+/// # Safety
+///
+/// `file` has to be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sg_file_image_info(file: *const SgFile, index: usize, out: *mut SgImageInfo) -> SgErrorCode {
+    guard_code(|| {
+        let file = match file.as_ref() {
+            Some(file) if !out.is_null() => file,
+            _ => return set_last_error(SgErrorCode::InvalidArgument, String::from("null pointer passed")),
+        };
+
+        let image = match file.metadata.images.get(index) {
+            Some(image) => image,
+            None => return set_last_error(SgErrorCode::IndexOutOfRange, format!("no image with index {}", index)),
+        };
+
+        *out = SgImageInfo {
+            id: image.id,
+            width: image.width,
+            height: image.height,
+            image_type: image.image_type,
+            bitmap_id: image.bitmap_id,
+            is_external: image.is_external(),
+            invert_offset: image.invert_offset,
+            x_offset: image.x_offset,
+            y_offset: image.y_offset,
+            anim_sprites: image.anim_sprites,
+            anim_speed_id: image.anim_speed_id,
+            is_reversible: image.is_reversible,
+            offset: image.offset,
+            length: image.length,
+            uncompressed_length: image.uncompressed_length,
+            alpha_offset: image.alpha_offset,
+            alpha_length: image.alpha_length,
+        };
+
+        SgErrorCode::Ok
+    })
+}
+
+/// Decode the image with the given index into the RGBA buffer provided by the caller.
+///
+/// Rows of the image are written `stride` bytes apart, which has to be at least `width * 4`.
+/// The buffer has to hold at least `(height - 1) * stride + width * 4` bytes.
+///
+/// # Safety
+///
+/// `file` has to be a valid handle and `buffer` has to point to at least `buffer_length` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sg_file_decode_image(file: *const SgFile, index: usize, buffer: *mut u8, buffer_length: usize, stride: usize) -> SgErrorCode {
+    guard_code(|| {
+        let file = match file.as_ref() {
+            Some(file) if !buffer.is_null() => file,
+            _ => return set_last_error(SgErrorCode::InvalidArgument, String::from("null pointer passed")),
+        };
+
+        let image = match file.metadata.images.get(index) {
+            Some(image) => image,
+            None => return set_last_error(SgErrorCode::IndexOutOfRange, format!("no image with index {}", index)),
+        };
+
+        let width = image.width as usize;
+        let height = image.height as usize;
+
+        if width > 0 && height > 0 {
+            let required_length = (height - 1).checked_mul(stride).and_then(|length| length.checked_add(width * 4));
+            if stride < width * 4 || required_length.is_none_or(|required_length| buffer_length < required_length) {
+                return set_last_error(SgErrorCode::BufferTooSmall, format!("buffer too small for a {}x{} image", width, height));
+            }
+        }
+
+        let path = file.metadata.get_555_file_path(image.bitmap_id as usize, image.is_external());
+        let mut reader = match File::open(path) {
+            Ok(data_file) => BufReader::new(data_file),
+            Err(err) => return error_code(err.into()),
+        };
+
+        let buffer = std::slice::from_raw_parts_mut(buffer, buffer_length);
+        let image_builder_factory = RegionImageBuilderFactory::new(buffer, stride, 0, 0);
+
+        match image.load_image(&mut reader, &image_builder_factory) {
+            Ok(()) => SgErrorCode::Ok,
+            Err(err) => error_code(err),
+        }
+    })
+}
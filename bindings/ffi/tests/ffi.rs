@@ -0,0 +1,73 @@
+#[path = "../../../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use sg_image_reader_ffi::*;
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::ptr;
+
+fn open(path: &str) -> Result<*mut SgFile, SgErrorCode> {
+    let path = CString::new(path).unwrap();
+    let mut file = ptr::null_mut();
+
+    match unsafe { sg_file_open(path.as_ptr(), &mut file) } {
+        SgErrorCode::Ok => Ok(file),
+        code => Err(code),
+    }
+}
+
+fn last_error_message() -> String {
+    unsafe { CStr::from_ptr(sg_last_error_message()) }.to_str().unwrap().to_string()
+}
+
+#[test]
+fn decodes_images_through_the_c_functions() {
+    let fixture = write_fixture("ffi", 0xd6, &[plain_image(4, 3), with_alpha_mask(sprite_image(5, 6))]);
+    let file = open(fixture.sg_path.to_str().unwrap()).unwrap();
+
+    assert_eq!(unsafe { sg_file_image_count(file) }, 3);
+
+    let mut info = MaybeUninit::<SgImageInfo>::uninit();
+    assert_eq!(unsafe { sg_file_image_info(file, 2, info.as_mut_ptr()) }, SgErrorCode::Ok);
+    let info = unsafe { info.assume_init() };
+    assert_eq!((info.id, info.width, info.height, info.image_type), (2, 5, 6, 256));
+    assert!(info.alpha_length > 0);
+
+    // Rows are written a stride apart, leaving the padding untouched
+    let stride = 24;
+    let mut buffer = vec![0xaa; stride * 6];
+    assert_eq!(unsafe { sg_file_decode_image(file, 2, buffer.as_mut_ptr(), buffer.len(), stride) }, SgErrorCode::Ok);
+    for (row, expected) in buffer.chunks(stride).zip(fixture.expected[2].chunks(20)) {
+        assert_eq!(&row[..20], expected);
+        assert_eq!(row[20..], [0xaa; 4]);
+    }
+
+    unsafe { sg_file_free(file) };
+}
+
+#[test]
+fn reports_errors_through_the_c_functions() {
+    let fixture = write_fixture("ffi_errors", 0xd5, &[plain_image(4, 3)]);
+    let file = open(fixture.sg_path.to_str().unwrap()).unwrap();
+    let mut buffer = vec![0; 48];
+
+    let decode = |index: usize, buffer: &mut [u8], buffer_length: usize, stride: usize| unsafe { sg_file_decode_image(file, index, buffer.as_mut_ptr(), buffer_length, stride) };
+
+    assert_eq!(decode(2, &mut buffer, 48, 16), SgErrorCode::IndexOutOfRange);
+    assert_eq!(last_error_message(), "no image with index 2");
+    assert_eq!(decode(1, &mut buffer, 47, 16), SgErrorCode::BufferTooSmall);
+    assert_eq!(decode(1, &mut buffer, 48, 12), SgErrorCode::BufferTooSmall);
+    assert_eq!(decode(1, &mut buffer, 48, usize::MAX), SgErrorCode::BufferTooSmall);
+    assert_eq!(last_error_message(), "buffer too small for a 4x3 image");
+    assert_eq!(unsafe { sg_file_decode_image(file, 1, ptr::null_mut(), 48, 16) }, SgErrorCode::InvalidArgument);
+
+    let mut info = MaybeUninit::<SgImageInfo>::uninit();
+    assert_eq!(unsafe { sg_file_image_info(file, 5, info.as_mut_ptr()) }, SgErrorCode::IndexOutOfRange);
+
+    unsafe { sg_file_free(file) };
+
+    let missing_path = fixture.sg_path.with_file_name("missing.sg3");
+    assert_eq!(open(missing_path.to_str().unwrap()), Err(SgErrorCode::IoError));
+    assert!(last_error_message().starts_with("IO error"));
+}
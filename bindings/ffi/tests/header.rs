@@ -0,0 +1,20 @@
+use std::fs;
+use std::path::Path;
+
+const GENERATED_HEADER: &str = include_str!(concat!(env!("OUT_DIR"), "/sg_image_reader.h"));
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/sg_image_reader.h");
+
+    if std::env::var_os("SG_IMAGE_READER_UPDATE_HEADER").is_some() {
+        fs::write(&path, GENERATED_HEADER).unwrap();
+    }
+
+    let committed_header = fs::read_to_string(&path).unwrap();
+    assert!(
+        committed_header == GENERATED_HEADER,
+        "{} is out of date, update it by running the test with SG_IMAGE_READER_UPDATE_HEADER=1",
+        path.display()
+    );
+}
@@ -0,0 +1,321 @@
+//! Python bindings for reading sg3 files.
+//!
+//! ```python
+//! import sg_image_reader
+//!
+//! sg_file = sg_image_reader.SgFile("C3.sg2")
+//!
+//! for image in sg_file.images:
+//!     print(image.id, image.image_type, image.unknown_a, image.unknown_f)
+//!
+//! # numpy array of shape (height, width, 4)
+//! pixels = sg_file.load_image(11)
+//! ```
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use numpy::{PyArray1, PyArray3, PyArrayMethods};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyIndexError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use sg_image_reader::{SgBitmapMetadata, SgFileMetadata, SgImageMetadata, VecImageBuilderFactory};
+
+create_exception!(sg_image_reader, SgImageError, PyException, "Error encountered when reading a sg file.");
+
+fn to_py_err(err: sg_image_reader::SgImageError) -> PyErr {
+    SgImageError::new_err(err.to_string())
+}
+
+/// Metadata of a sg file, along with its bitmaps and images.
+#[pyclass(name = "SgFile", module = "sg_image_reader", frozen)]
+struct PySgFile {
+    metadata: SgFileMetadata,
+}
+
+#[pymethods]
+impl PySgFile {
+    /// Load metadata from the sg file on the given path.
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let metadata = SgFileMetadata::load_metadata_from_path(path).map_err(to_py_err)?;
+        Ok(PySgFile { metadata })
+    }
+
+    #[getter]
+    fn folder(&self) -> &str {
+        &self.metadata.folder
+    }
+
+    #[getter]
+    fn filename(&self) -> &str {
+        &self.metadata.filename
+    }
+
+    #[getter]
+    fn file_size(&self) -> u32 {
+        self.metadata.file_size
+    }
+
+    #[getter]
+    fn version(&self) -> u32 {
+        self.metadata.version
+    }
+
+    #[getter]
+    fn unknown(&self) -> u32 {
+        self.metadata.unknown
+    }
+
+    #[getter]
+    fn max_image_count(&self) -> u32 {
+        self.metadata.max_image_count
+    }
+
+    #[getter]
+    fn bitmap_records_without_system(&self) -> u32 {
+        self.metadata.bitmap_records_without_system
+    }
+
+    #[getter]
+    fn total_file_size(&self) -> u32 {
+        self.metadata.total_file_size
+    }
+
+    #[getter]
+    fn file_size_555(&self) -> u32 {
+        self.metadata.file_size_555
+    }
+
+    #[getter]
+    fn file_size_external(&self) -> u32 {
+        self.metadata.file_size_external
+    }
+
+    #[getter]
+    fn unknown_header(&self) -> [u32; 10] {
+        self.metadata.unknown_header
+    }
+
+    /// Index of the first image of each image group.
+    #[getter]
+    fn group_image_ids(&self) -> Vec<u16> {
+        self.metadata.group_image_ids.clone()
+    }
+
+    /// Get index of the image at the given offset within the given image group.
+    fn group_image_index(&self, group: u16, offset: u16) -> Option<usize> {
+        self.metadata.group_image_index(group, offset)
+    }
+
+    /// Get the image group and the offset within it of the image with the given index.
+    fn image_group(&self, index: usize) -> Option<(u16, u16)> {
+        self.metadata.image_group(index)
+    }
+
+    /// Metadata of all the bitmaps.
+    #[getter]
+    fn bitmaps(&self) -> Vec<PySgBitmap> {
+        self.metadata.bitmaps.iter().map(PySgBitmap::from).collect()
+    }
+
+    /// Image records with mirrored images resolved to the record of their source image.
+    #[getter]
+    fn images(&self) -> Vec<PySgImage> {
+        self.metadata.images.iter().map(PySgImage::from).collect()
+    }
+
+    /// Image records as stored in the file.
+    #[getter]
+    fn raw_images(&self) -> Vec<PySgImage> {
+        self.metadata.raw_images.iter().map(PySgImage::from).collect()
+    }
+
+    /// Get path to the file containing pixel data of the image with the given index.
+    fn data_file_path(&self, index: usize) -> PyResult<PathBuf> {
+        let image = self.image(index)?;
+        Ok(self.metadata.get_555_file_path(image.bitmap_id as usize, image.is_external()))
+    }
+
+    /// Load pixel data of the image with the given index as a numpy array of shape (height, width, 4).
+    fn load_image<'py>(&self, py: Python<'py>, index: usize) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let image = self.image(index)?;
+        let pixels = py.detach(|| self.load_pixels(image))?;
+        PyArray1::from_vec(py, pixels).reshape([image.height as usize, image.width as usize, 4])
+    }
+
+    /// Load pixel data of the image with the given index as RGBA bytes.
+    fn load_image_bytes<'py>(&self, py: Python<'py>, index: usize) -> PyResult<Bound<'py, PyBytes>> {
+        let image = self.image(index)?;
+        let pixels = py.detach(|| self.load_pixels(image))?;
+        Ok(PyBytes::new(py, &pixels))
+    }
+
+    fn __len__(&self) -> usize {
+        self.metadata.images.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SgFile(filename={:?}, version={:#x}, bitmaps={}, images={})",
+            self.metadata.filename,
+            self.metadata.version,
+            self.metadata.bitmaps.len(),
+            self.metadata.images.len()
+        )
+    }
+}
+
+impl PySgFile {
+    fn image(&self, index: usize) -> PyResult<&SgImageMetadata> {
+        self.metadata.images.get(index).ok_or_else(|| PyIndexError::new_err(format!("no image with index {}", index)))
+    }
+
+    fn load_pixels(&self, image: &SgImageMetadata) -> PyResult<Vec<u8>> {
+        let path = self.metadata.get_555_file_path(image.bitmap_id as usize, image.is_external());
+        let mut reader = BufReader::new(File::open(path)?);
+        image.load_image(&mut reader, &VecImageBuilderFactory).map_err(to_py_err)
+    }
+}
+
+/// Metadata of a bitmap.
+#[pyclass(name = "SgBitmap", module = "sg_image_reader", frozen, get_all)]
+#[derive(Clone)]
+struct PySgBitmap {
+    id: u32,
+    external_filename: String,
+    comment: String,
+    width: u32,
+    height: u32,
+    num_images: u32,
+    start_index: u32,
+    end_index: u32,
+    image_id: u32,
+    unknown_a: u32,
+    unknown_b: u32,
+    unknown_c: u32,
+    unknown_d: u32,
+    image_width: u32,
+    image_height: u32,
+    file_size_555: u32,
+    total_file_size: u32,
+    file_size_external: u32,
+    unknown_e: [u8; 24],
+}
+
+#[pymethods]
+impl PySgBitmap {
+    fn __repr__(&self) -> String {
+        format!("SgBitmap(id={}, external_filename={:?}, comment={:?})", self.id, self.external_filename, self.comment)
+    }
+}
+
+impl From<&SgBitmapMetadata> for PySgBitmap {
+    fn from(bitmap: &SgBitmapMetadata) -> Self {
+        PySgBitmap {
+            id: bitmap.id,
+            external_filename: bitmap.external_filename.clone(),
+            comment: bitmap.comment.clone(),
+            width: bitmap.width,
+            height: bitmap.height,
+            num_images: bitmap.num_images,
+            start_index: bitmap.start_index,
+            end_index: bitmap.end_index,
+            image_id: bitmap.image_id,
+            unknown_a: bitmap.unknown_a,
+            unknown_b: bitmap.unknown_b,
+            unknown_c: bitmap.unknown_c,
+            unknown_d: bitmap.unknown_d,
+            image_width: bitmap.image_width,
+            image_height: bitmap.image_height,
+            file_size_555: bitmap.file_size_555,
+            total_file_size: bitmap.total_file_size,
+            file_size_external: bitmap.file_size_external,
+            unknown_e: bitmap.unknown_e,
+        }
+    }
+}
+
+/// Metadata of an image.
+#[pyclass(name = "SgImage", module = "sg_image_reader", frozen, get_all)]
+#[derive(Clone)]
+struct PySgImage {
+    id: u32,
+    offset: u32,
+    length: u32,
+    uncompressed_length: u32,
+    zeroes: [u8; 4],
+    invert_offset: i32,
+    width: u16,
+    height: u16,
+    unknown_a: [u16; 3],
+    anim_sprites: u16,
+    unknown_b: u16,
+    x_offset: u16,
+    y_offset: u16,
+    unknown_c: [u8; 10],
+    is_reversible: u8,
+    unknown_d: u8,
+    image_type: u16,
+    flags: [u8; 4],
+    bitmap_id: u8,
+    unknown_e: u8,
+    anim_speed_id: u8,
+    unknown_f: [u8; 5],
+    alpha_offset: u32,
+    alpha_length: u32,
+    is_external: bool,
+    mirror_source: Option<usize>,
+}
+
+#[pymethods]
+impl PySgImage {
+    fn __repr__(&self) -> String {
+        format!("SgImage(id={}, image_type={}, width={}, height={})", self.id, self.image_type, self.width, self.height)
+    }
+}
+
+impl From<&SgImageMetadata> for PySgImage {
+    fn from(image: &SgImageMetadata) -> Self {
+        PySgImage {
+            id: image.id,
+            offset: image.offset,
+            length: image.length,
+            uncompressed_length: image.uncompressed_length,
+            zeroes: image.zeroes,
+            invert_offset: image.invert_offset,
+            width: image.width,
+            height: image.height,
+            unknown_a: image.unknown_a,
+            anim_sprites: image.anim_sprites,
+            unknown_b: image.unknown_b,
+            x_offset: image.x_offset,
+            y_offset: image.y_offset,
+            unknown_c: image.unknown_c,
+            is_reversible: image.is_reversible,
+            unknown_d: image.unknown_d,
+            image_type: image.image_type,
+            flags: image.flags,
+            bitmap_id: image.bitmap_id,
+            unknown_e: image.unknown_e,
+            anim_speed_id: image.anim_speed_id,
+            unknown_f: image.unknown_f,
+            alpha_offset: image.alpha_offset,
+            alpha_length: image.alpha_length,
+            is_external: image.is_external(),
+            mirror_source: image.mirror_source(),
+        }
+    }
+}
+
+/// Library for reading sg3 files used in some Impressions Games city building games.
+#[pymodule]
+#[pyo3(name = "sg_image_reader")]
+pub fn sg_image_reader_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySgFile>()?;
+    m.add_class::<PySgBitmap>()?;
+    m.add_class::<PySgImage>()?;
+    m.add("SgImageError", m.py().get_type::<SgImageError>())?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Tests of the module run in an interpreter embedded into the test, without numpy installed.
+#[path = "../../../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyModule};
+use sg_image_reader_py::sg_image_reader_module;
+use std::ffi::CString;
+
+/// Run the Python code with the module imported as `sg_image_reader` and the given variables set.
+fn run_python(code: &str, set_variables: impl for<'py> FnOnce(&Bound<'py, PyDict>) -> PyResult<()>) {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let module = PyModule::new(py, "sg_image_reader")?;
+        sg_image_reader_module(&module)?;
+
+        let locals = PyDict::new(py);
+        locals.set_item("sg_image_reader", module)?;
+        set_variables(&locals)?;
+
+        py.run(&CString::new(code).unwrap(), None, Some(&locals))
+    })
+    .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[test]
+fn loads_files_and_images() {
+    let sprite = sprite_image(7, 5);
+    let fixture = write_fixture("python", 0xd6, &[plain_image(4, 3), sprite.clone(), mirrored_image(&sprite, 1)]);
+
+    let code = r#"
+sg_file = sg_image_reader.SgFile(path)
+
+assert (sg_file.version, len(sg_file)) == (0xd6, 4)
+assert sg_file.bitmaps[0].external_filename == "python.bmp"
+assert (sg_file.images[2].width, sg_file.images[2].height, sg_file.images[2].image_type) == (7, 5, 256)
+assert sg_file.images[3].mirror_source == 2 and sg_file.raw_images[3].offset == 0
+assert sg_file.data_file_path(1).name == "python.555"
+
+for index, pixels in enumerate(expected):
+    assert sg_file.load_image_bytes(index) == pixels, index
+
+try:
+    sg_file.load_image_bytes(4)
+    assert False
+except IndexError as err:
+    assert str(err) == "no image with index 4"
+"#;
+
+    run_python(code, |locals| {
+        let py = locals.py();
+        let expected: Vec<_> = fixture.expected.iter().map(|pixels| PyBytes::new(py, pixels)).collect();
+        locals.set_item("path", &fixture.sg_path)?;
+        locals.set_item("expected", expected)
+    });
+}
+
+#[test]
+fn raises_errors_of_invalid_files() {
+    let fixture = write_fixture("python_invalid", 0xd5, &[plain_image(4, 3)]);
+    // Pixel data does not start with a valid header
+    let invalid_path = fixture.sg_path.with_file_name("python_invalid.555");
+
+    let code = r#"
+try:
+    sg_image_reader.SgFile(path)
+    assert False
+except sg_image_reader.SgImageError as err:
+    assert str(err).startswith("unsupported sg file version"), str(err)
+"#;
+
+    run_python(code, |locals| locals.set_item("path", &invalid_path));
+}
@@ -0,0 +1,156 @@
+//! WebAssembly bindings for reading sg3 files in the browser.
+//!
+//! The files are passed in as `ArrayBuffer` contents, as no file system is available:
+//! ```js
+//! const sgFile = new SgFile(new Uint8Array(await sg3File.arrayBuffer()), sg3File.name);
+//!
+//! for (const image of sgFile.images()) {
+//!     // Name of the .555 file to be provided for decoding the image
+//!     const dataFileName = sgFile.dataFileName(image.index);
+//! }
+//!
+//! const pixels = sgFile.decodeImage(11, new Uint8Array(await dataFile.arrayBuffer()));
+//! const imageData = new ImageData(pixels, sgFile.images()[11].width);
+//! ```
+use js_sys::Uint8ClampedArray;
+use sg_image_reader::{LoadOptions, SgBitmapMetadata, SgFileMetadata, SgImageError, SgImageMetadata, VecImageBuilderFactory};
+use wasm_bindgen::prelude::*;
+
+/// Metadata of a sg file, parsed from the contents of the file.
+#[wasm_bindgen]
+pub struct SgFile {
+    metadata: SgFileMetadata,
+}
+
+#[wasm_bindgen]
+impl SgFile {
+    /// Parse metadata from the contents of a sg file with the given name.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8], filename: String) -> Result<SgFile, JsError> {
+        let metadata = SgFileMetadata::load_metadata_from_bytes(data, String::new(), filename).map_err(to_js_error)?;
+        Ok(SgFile { metadata })
+    }
+
+    /// Version of the sg file format.
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u32 {
+        self.metadata.version
+    }
+
+    /// Number of images in the file.
+    #[wasm_bindgen(getter, js_name = imageCount)]
+    pub fn image_count(&self) -> usize {
+        self.metadata.images.len()
+    }
+
+    /// List metadata of all the bitmaps.
+    pub fn bitmaps(&self) -> Vec<SgBitmap> {
+        self.metadata.bitmaps.iter().map(SgBitmap::from).collect()
+    }
+
+    /// List metadata of all the images.
+    pub fn images(&self) -> Vec<SgImage> {
+        self.metadata.images.iter().map(SgImage::from).collect()
+    }
+
+    /// Name of the .555 file containing pixel data of the image with the given index.
+    #[wasm_bindgen(js_name = dataFileName)]
+    pub fn data_file_name(&self, index: usize) -> Result<String, JsError> {
+        let image = self.image(index).map_err(|message| JsError::new(&message))?;
+        Ok(self.metadata.get_555_file_name(image.bitmap_id as usize, image.is_external()))
+    }
+
+    /// Decode the image with the given index from the contents of its .555 file into RGBA pixels, as used by `ImageData`.
+    #[wasm_bindgen(js_name = decodeImage)]
+    pub fn decode_image(&self, index: usize, data: &[u8]) -> Result<Uint8ClampedArray, JsError> {
+        let pixels = self.decode_pixels(index, data).map_err(|message| JsError::new(&message))?;
+        Ok(Uint8ClampedArray::from(pixels.as_slice()))
+    }
+}
+
+impl SgFile {
+    /// Decode the image with the given index from the contents of its .555 file into RGBA pixels, without going through JavaScript types.
+    ///
+    /// Failures are described by the message of the error to be thrown.
+    pub fn decode_pixels(&self, index: usize, data: &[u8]) -> Result<Vec<u8>, String> {
+        let image = self.image(index)?;
+        image.load_image_from_bytes(data, &VecImageBuilderFactory, &LoadOptions::default()).map_err(|err| err.to_string())
+    }
+
+    fn image(&self, index: usize) -> Result<&SgImageMetadata, String> {
+        self.metadata.images.get(index).ok_or_else(|| format!("no image with index {}", index))
+    }
+}
+
+/// Metadata of a bitmap.
+#[wasm_bindgen(getter_with_clone)]
+pub struct SgBitmap {
+    pub id: u32,
+    #[wasm_bindgen(js_name = externalFilename)]
+    pub external_filename: String,
+    pub comment: String,
+    pub width: u32,
+    pub height: u32,
+    #[wasm_bindgen(js_name = numImages)]
+    pub num_images: u32,
+    #[wasm_bindgen(js_name = startIndex)]
+    pub start_index: u32,
+    #[wasm_bindgen(js_name = endIndex)]
+    pub end_index: u32,
+}
+
+impl From<&SgBitmapMetadata> for SgBitmap {
+    fn from(bitmap: &SgBitmapMetadata) -> Self {
+        SgBitmap {
+            id: bitmap.id,
+            external_filename: bitmap.external_filename.clone(),
+            comment: bitmap.comment.clone(),
+            width: bitmap.width,
+            height: bitmap.height,
+            num_images: bitmap.num_images,
+            start_index: bitmap.start_index,
+            end_index: bitmap.end_index,
+        }
+    }
+}
+
+/// Metadata of an image.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct SgImage {
+    pub index: u32,
+    pub width: u16,
+    pub height: u16,
+    #[wasm_bindgen(js_name = imageType)]
+    pub image_type: u16,
+    #[wasm_bindgen(js_name = bitmapId)]
+    pub bitmap_id: u8,
+    #[wasm_bindgen(js_name = xOffset)]
+    pub x_offset: u16,
+    #[wasm_bindgen(js_name = yOffset)]
+    pub y_offset: u16,
+    #[wasm_bindgen(js_name = isExternal)]
+    pub is_external: bool,
+    #[wasm_bindgen(js_name = isMirrored)]
+    pub is_mirrored: bool,
+}
+
+impl From<&SgImageMetadata> for SgImage {
+    fn from(image: &SgImageMetadata) -> Self {
+        SgImage {
+            index: image.id,
+            width: image.width,
+            height: image.height,
+            image_type: image.image_type,
+            bitmap_id: image.bitmap_id,
+            x_offset: image.x_offset,
+            y_offset: image.y_offset,
+            is_external: image.is_external(),
+            is_mirrored: image.is_mirrored(),
+        }
+    }
+}
+
+fn to_js_error(err: SgImageError) -> JsError {
+    JsError::new(&err.to_string())
+}
@@ -0,0 +1,32 @@
+//! Tests of the bindings run natively, which cannot create JavaScript values such as errors or arrays.
+#[path = "../../../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use sg_image_reader_wasm::SgFile;
+
+#[test]
+fn parses_files_and_decodes_images() {
+    let sprite = sprite_image(7, 5);
+    let fixture = write_fixture("wasm", 0xd6, &[plain_image(4, 3), sprite.clone(), mirrored_image(&sprite, 1)]);
+    let data = std::fs::read(&fixture.sg_path).unwrap();
+
+    let sg_file = SgFile::new(&data, String::from("wasm.sg3")).unwrap();
+
+    assert_eq!((sg_file.version(), sg_file.image_count()), (0xd6, 4));
+    assert_eq!(sg_file.bitmaps()[0].external_filename, "wasm.bmp");
+
+    let images = sg_file.images();
+    assert_eq!((images[2].index, images[2].width, images[2].height, images[2].image_type), (2, 7, 5, 256));
+    assert!(images[3].is_mirrored && !images[2].is_mirrored);
+
+    assert_eq!(sg_file.data_file_name(1).unwrap(), "wasm.555");
+    let data_555 = std::fs::read(fixture.sg_path.with_file_name("wasm.555")).unwrap();
+
+    for index in 1..4 {
+        assert_eq!(sg_file.decode_pixels(index, &data_555), Ok(fixture.expected[index].clone()), "pixels of image {}", index);
+    }
+
+    assert_eq!(sg_file.decode_pixels(4, &data_555), Err(String::from("no image with index 4")));
+    assert_eq!(sg_file.decode_pixels(1, &data_555[..10]), Err(String::from("unexpected end of data")));
+}
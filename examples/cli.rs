@@ -0,0 +1,113 @@
+use sg_image_reader::{SgFileDiff, SgFileMetadata, VecImageBuilderFactory};
+use std::env;
+use std::process::exit;
+
+const USAGE: &str = "Usage:
+    cli lint <sg file>...                          Check metadata of the files for inconsistencies
+    cli stats [--format text|csv|json] <sg file>   Print statistics of the images of the file
+    cli diff [--pixels] <old sg file> <new sg file>   Print differences between the files, comparing decoded pixels with --pixels
+    cli diff --visual <folder> <old sg file> <new sg file>
+                                                   Also save a PNG for each image whose pixels differ (requires the `image` feature)";
+
+/// Print findings of the metadata linter for each of the files, returning whether all of them are consistent.
+fn lint(paths: &[String]) -> sg_image_reader::Result<bool> {
+    let mut is_consistent = true;
+
+    for path in paths {
+        let sg_file = SgFileMetadata::load_metadata_from_path(path)?;
+        let findings = sg_file.lint();
+
+        println!("{}: {} findings", path, findings.len());
+        for finding in &findings {
+            println!("    {}", finding);
+        }
+
+        is_consistent &= findings.is_empty();
+    }
+
+    Ok(is_consistent)
+}
+
+/// Print statistics of the images of the file in the given format.
+fn stats(format: &str, path: &str) -> sg_image_reader::Result<bool> {
+    let stats = SgFileMetadata::load_metadata_from_path(path)?.stats();
+
+    match format {
+        "text" => print!("{}", stats.to_text()),
+        "csv" => print!("{}", stats.to_csv()),
+        "json" => println!("{}", stats.to_json()),
+        _ => {
+            println!("{}", USAGE);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Print differences between the files, comparing decoded pixels if requested and saving images of the differences into the given folder.
+fn diff(old_path: &str, new_path: &str, compare_pixels: bool, visual_folder: Option<&str>) -> sg_image_reader::Result<bool> {
+    let diff = if compare_pixels || visual_folder.is_some() {
+        let (old, old_pixels) = SgFileMetadata::load_fully(old_path, &VecImageBuilderFactory)?;
+        let (new, new_pixels) = SgFileMetadata::load_fully(new_path, &VecImageBuilderFactory)?;
+        let diff = old.diff_with_pixels(&old_pixels, &new, &new_pixels);
+
+        if let Some(folder) = visual_folder {
+            save_visual_diffs(&diff, folder, (&old, &old_pixels), (&new, &new_pixels))?;
+        }
+
+        diff
+    } else {
+        SgFileMetadata::load_metadata_from_path(old_path)?.diff(&SgFileMetadata::load_metadata_from_path(new_path)?)
+    };
+
+    print!("{}", diff);
+
+    Ok(diff.is_empty())
+}
+
+#[cfg(feature = "image")]
+fn save_visual_diffs(diff: &SgFileDiff, folder: &str, old: (&SgFileMetadata, &[Vec<u8>]), new: (&SgFileMetadata, &[Vec<u8>])) -> sg_image_reader::Result<()> {
+    std::fs::create_dir_all(folder)?;
+
+    for &(old_index, new_index) in &diff.changed_pixels {
+        let diff_image = SgFileDiff::visual_diff(&old.0.images[old_index], &old.1[old_index], &new.0.images[new_index], &new.1[new_index]);
+        diff_image.save(format!("{}/{}_{}.png", folder, old_index, new_index))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "image"))]
+fn save_visual_diffs(_diff: &SgFileDiff, _folder: &str, _old: (&SgFileMetadata, &[Vec<u8>]), _new: (&SgFileMetadata, &[Vec<u8>])) -> sg_image_reader::Result<()> {
+    eprintln!("Saving visual diffs requires the `image` feature");
+    Ok(())
+}
+
+fn run(args: &[String]) -> sg_image_reader::Result<bool> {
+    match args {
+        [command, paths @ ..] if command == "lint" && !paths.is_empty() => lint(paths),
+        [command, path] if command == "stats" => stats("text", path),
+        [command, option, format, path] if command == "stats" && option == "--format" => stats(format, path),
+        [command, old, new] if command == "diff" => diff(old, new, false, None),
+        [command, option, old, new] if command == "diff" && option == "--pixels" => diff(old, new, true, None),
+        [command, option, folder, old, new] if command == "diff" && option == "--visual" => diff(old, new, true, Some(folder)),
+        _ => {
+            println!("{}", USAGE);
+            Ok(false)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(err) => {
+            eprintln!("Failed: {}", err);
+            exit(2);
+        }
+    }
+}
@@ -12,17 +12,15 @@ fn run() -> Result<()> {
 
     let paths = fs::read_dir(s.trim()).unwrap();
 
-    for path in paths {
-        if let Ok(dir) = path {
-            if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3")) {
-                println!("Verifying {:?}", dir.path());
-                let start = Instant::now();
-                if let Err(err) = SgFileMetadata::load_fully(dir.path(), &VecImageBuilderFactory) {
-                    println!("Failed to load: {err:?}");
-                };
-                let elapsed_time = start.elapsed();
-                println!("Finished in {}ms", elapsed_time.as_millis());
-            }
+    for dir in paths.flatten() {
+        if dir.path().as_path().extension().map_or_else(|| false, |ext| ext.eq("sg3")) {
+            println!("Verifying {:?}", dir.path());
+            let start = Instant::now();
+            if let Err(err) = SgFileMetadata::load_fully(dir.path(), &VecImageBuilderFactory) {
+                println!("Failed to load: {err:?}");
+            };
+            let elapsed_time = start.elapsed();
+            println!("Finished in {}ms", elapsed_time.as_millis());
         }
     }
 
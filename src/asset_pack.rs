@@ -0,0 +1,145 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use image::ImageFormat;
+
+use crate::sg_file::DataFileReaders;
+use crate::{LoadOptions, RgbaImageBuilderFactory, Result, SgBitmapMetadata, SgFileMetadata, SgImageError, SgImageMetadata};
+
+impl SgFileMetadata {
+    /// Export all bitmaps as asset groups in the layout used by Augustus, see [SgFileMetadata::export_bitmap_assets].
+    ///
+    /// Requires the `image` feature.
+    pub fn export_assets<P: AsRef<Path>>(&self, assets_folder: P) -> Result<()> {
+        for bitmap_id in 0..self.bitmaps.len() {
+            self.export_bitmap_assets(bitmap_id, assets_folder.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Export images of the given bitmap as an asset group in the layout used by Augustus.
+    ///
+    /// Writes `<group>/<group>.xml` describing the images, along with a PNG file for each of them, to the given folder.
+    /// The group is named after the bitmap comment (or file name if there is none) and images after the group and their position in the bitmap.
+    /// Animation frames following an image are listed as an animation of that image instead of separate images.
+    ///
+    /// Requires the `image` feature.
+    pub fn export_bitmap_assets<P: AsRef<Path>>(&self, bitmap_id: usize, assets_folder: P) -> Result<()> {
+        let group = self.asset_group_name(bitmap_id)?;
+        let folder = assets_folder.as_ref().join(&group);
+        fs::create_dir_all(&folder)?;
+
+        let indexes: Vec<usize> = (0..self.images.len()).filter(|index| self.images[*index].bitmap_id as usize == bitmap_id).collect();
+        let image_name = |position: usize| format!("{}_{}", group, position);
+
+        let mut readers = DataFileReaders::default();
+
+        for (position, index) in indexes.iter().enumerate() {
+            let image = &self.images[*index];
+            if image.width == 0 || image.height == 0 {
+                continue;
+            }
+
+            let path = self.get_555_file_path(bitmap_id, image.is_external());
+            let rgba_image = readers.load_image(image, self.bitmaps.get(bitmap_id), &path, &RgbaImageBuilderFactory, &LoadOptions::default())?;
+            rgba_image.save_with_format(folder.join(format!("{}.png", image_name(position))), ImageFormat::Png)?;
+        }
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\"?>\n<!DOCTYPE assetlist>\n");
+        let _ = writeln!(xml, "<assetlist name=\"{}\">", group);
+
+        let mut position = 0;
+        while position < indexes.len() {
+            let image = &self.images[indexes[position]];
+            let frames = (image.anim_sprites as usize).min(indexes.len() - position - 1);
+
+            if image.width != 0 && image.height != 0 {
+                let _ = write!(xml, "\t<image id=\"{}\" src=\"{}\"", image_name(position), image_name(position));
+                write_image_attributes(&mut xml, image);
+
+                // Frames without pixels have no PNG file to refer to
+                let frame_positions: Vec<usize> = (position + 1..=position + frames)
+                    .filter(|frame| {
+                        let frame = &self.images[indexes[*frame]];
+                        frame.width != 0 && frame.height != 0
+                    })
+                    .collect();
+
+                if frame_positions.is_empty() {
+                    xml.push_str("/>\n");
+                } else {
+                    let first_frame = &self.images[indexes[frame_positions[0]]];
+                    let _ = writeln!(
+                        xml,
+                        ">\n\t\t<animation frames=\"{}\" speed=\"{}\" reversible=\"{}\" x=\"{}\" y=\"{}\">",
+                        frame_positions.len(),
+                        image.anim_speed_id,
+                        image.is_reversible != 0,
+                        first_frame.x_offset,
+                        first_frame.y_offset
+                    );
+                    for frame in frame_positions {
+                        let _ = writeln!(xml, "\t\t\t<frame src=\"{}\"/>", image_name(frame));
+                    }
+                    xml.push_str("\t\t</animation>\n\t</image>\n");
+                }
+            }
+
+            position += frames + 1;
+        }
+
+        xml.push_str("</assetlist>\n");
+        fs::write(folder.join(format!("{}.xml", group)), xml)?;
+
+        Ok(())
+    }
+
+    /// Get name of the asset group the given bitmap is exported as, made of characters safe to use in file names.
+    ///
+    /// Requires the `image` feature.
+    pub fn asset_group_name(&self, bitmap_id: usize) -> Result<String> {
+        let bitmap = self.bitmaps.get(bitmap_id).ok_or(SgImageError::InvalidBitmapId(bitmap_id))?;
+        let name = sanitize_name(bitmap_name(bitmap));
+
+        // Keep names of groups exported from the same file distinct
+        let is_duplicate = self.bitmaps.iter().any(|other| other.id != bitmap.id && sanitize_name(bitmap_name(other)) == name);
+
+        let name = if name.is_empty() {
+            format!("Bitmap_{}", bitmap.id)
+        } else if is_duplicate {
+            format!("{}_{}", name, bitmap.id)
+        } else {
+            name
+        };
+
+        Ok(name)
+    }
+}
+
+fn bitmap_name(bitmap: &SgBitmapMetadata) -> &str {
+    if bitmap.comment.trim().is_empty() {
+        bitmap.external_filename.rsplit_once('.').map_or(&bitmap.external_filename, |(stem, _)| stem)
+    } else {
+        &bitmap.comment
+    }
+}
+
+fn sanitize_name(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    String::from(name.trim_matches('_'))
+}
+
+fn write_image_attributes(xml: &mut String, image: &SgImageMetadata) {
+    let _ = write!(xml, " width=\"{}\" height=\"{}\"", image.width, image.height);
+
+    if image.x_offset != 0 || image.y_offset != 0 {
+        let _ = write!(xml, " x_offset=\"{}\" y_offset=\"{}\"", image.x_offset, image.y_offset);
+    }
+
+    if let Some(size) = image.isometric_size() {
+        let _ = write!(xml, " isometric=\"true\" size=\"{}\"", size);
+    }
+}
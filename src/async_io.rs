@@ -0,0 +1,54 @@
+use std::io::{BufReader, Cursor, SeekFrom};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{LoadOptions, Result, SgFileMetadata, SgImageMetadata, TryImageBuilderFactory};
+
+impl SgFileMetadata {
+    /// Load metadata from provided asynchronous reader.
+    ///
+    /// The whole contents of the reader are read into memory from its start before being parsed.
+    ///
+    /// Requires the `async` feature.
+    pub async fn load_metadata_from_async_reader<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, folder: String, filename: String) -> Result<Self> {
+        let mut data = Vec::new();
+        reader.seek(SeekFrom::Start(0)).await?;
+        reader.read_to_end(&mut data).await?;
+
+        Self::load_metadata_from_reader(&mut BufReader::new(Cursor::new(data)), folder, filename)
+    }
+}
+
+impl SgImageMetadata {
+    /// Load pixel data for this image from the provided asynchronous reader.
+    ///
+    /// Requires the `async` feature.
+    pub async fn load_image_async<T, F: TryImageBuilderFactory<T>, R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        reader: &mut R,
+        image_builder_factory: &F,
+    ) -> Result<T> {
+        self.load_image_with_options_async(reader, image_builder_factory, &LoadOptions::default()).await
+    }
+
+    /// Load pixel data for this image from the provided asynchronous reader, converting pixels as described by the given options.
+    ///
+    /// Pixel data of the image is read into memory before being decoded.
+    ///
+    /// Requires the `async` feature.
+    pub async fn load_image_with_options_async<T, F: TryImageBuilderFactory<T>, R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        reader: &mut R,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        let mut data = vec![0; self.data_length()];
+
+        if self.has_pixel_data() {
+            reader.seek(SeekFrom::Start(self.data_position())).await?;
+            reader.read_exact(&mut data).await?;
+        }
+
+        self.decode_image(&mut data.as_slice(), None, image_builder_factory, options)
+    }
+}
@@ -0,0 +1,250 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+
+use crate::{SgFileMetadata, SgImageMetadata};
+
+/// Change of a bitmap between two sg files, see [SgFileMetadata::diff].
+///
+/// Bitmaps are matched by their names, in the order they appear in the files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitmapChange {
+    /// The bitmap with the given index in the new file is not in the old file.
+    Added { new: usize, name: String },
+    /// The bitmap with the given index in the old file is not in the new file.
+    Removed { old: usize, name: String },
+    /// The bitmap moved from the given index in the old file to the given index in the new file.
+    Moved { old: usize, new: usize, name: String },
+}
+
+/// Field of an image record with different values in the two files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// Image matched between the two files, with the fields of its record that changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageChange {
+    /// Index of the image in the old file.
+    pub old: usize,
+    /// Index of the image in the new file.
+    pub new: usize,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Differences between two sg files.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SgFileDiff {
+    pub bitmaps: Vec<BitmapChange>,
+    /// Indexes of the images of the new file that have no matching image in the old file.
+    pub added_images: Vec<usize>,
+    /// Indexes of the images of the old file that have no matching image in the new file.
+    pub removed_images: Vec<usize>,
+    /// Matched images whose records differ in any field (apart from the image id).
+    pub changed_images: Vec<ImageChange>,
+    /// Indexes of matched images (in the old and the new file) whose decoded pixels differ, see [SgFileMetadata::diff_with_pixels].
+    pub changed_pixels: Vec<(usize, usize)>,
+    /// Indexes of all the matched images (in the old and the new file).
+    pub matched_images: Vec<(usize, usize)>,
+}
+
+impl SgFileDiff {
+    /// Checks if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.bitmaps.is_empty() && self.added_images.is_empty() && self.removed_images.is_empty() && self.changed_images.is_empty() && self.changed_pixels.is_empty()
+    }
+}
+
+impl Display for SgFileDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for change in &self.bitmaps {
+            match change {
+                BitmapChange::Added { new, name } => writeln!(f, "+ bitmap {} {:?}", new, name)?,
+                BitmapChange::Removed { old, name } => writeln!(f, "- bitmap {} {:?}", old, name)?,
+                BitmapChange::Moved { old, new, name } => writeln!(f, "~ bitmap {} -> {} {:?}", old, new, name)?,
+            }
+        }
+
+        for new in &self.added_images {
+            writeln!(f, "+ image {}", new)?;
+        }
+
+        for old in &self.removed_images {
+            writeln!(f, "- image {}", old)?;
+        }
+
+        for change in &self.changed_images {
+            writeln!(f, "~ image {} -> {}", change.old, change.new)?;
+            for field in &change.fields {
+                writeln!(f, "    {}: {} -> {}", field.field, field.old, field.new)?;
+            }
+        }
+
+        for (old, new) in &self.changed_pixels {
+            writeln!(f, "~ pixels of image {} -> {}", old, new)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SgFileMetadata {
+    /// Compare the metadata with the metadata of another (newer) file.
+    ///
+    /// Bitmaps are matched by their names and images by their position within the matched bitmaps.
+    pub fn diff(&self, new: &SgFileMetadata) -> SgFileDiff {
+        let mut diff = SgFileDiff::default();
+        let mut new_matched = alloc::vec![false; new.bitmaps.len()];
+
+        for (old_bitmap, bitmap) in self.bitmaps.iter().enumerate() {
+            let new_bitmap = (0..new.bitmaps.len()).find(|&i| !new_matched[i] && new.bitmaps[i].external_filename == bitmap.external_filename);
+
+            let new_bitmap = match new_bitmap {
+                Some(new_bitmap) => new_bitmap,
+                None => {
+                    diff.bitmaps.push(BitmapChange::Removed { old: old_bitmap, name: bitmap.external_filename.clone() });
+                    diff.removed_images.extend(self.bitmap_images(old_bitmap));
+                    continue;
+                }
+            };
+
+            new_matched[new_bitmap] = true;
+            if new_bitmap != old_bitmap {
+                diff.bitmaps.push(BitmapChange::Moved { old: old_bitmap, new: new_bitmap, name: bitmap.external_filename.clone() });
+            }
+
+            let old_images = self.bitmap_images(old_bitmap);
+            let new_images = new.bitmap_images(new_bitmap);
+
+            diff.removed_images.extend(old_images.iter().skip(new_images.len()));
+            diff.added_images.extend(new_images.iter().skip(old_images.len()));
+
+            for (&old, &new_index) in old_images.iter().zip(new_images.iter()) {
+                diff.matched_images.push((old, new_index));
+
+                let fields = Self::diff_fields(&self.raw_images[old], &new.raw_images[new_index]);
+                if !fields.is_empty() {
+                    diff.changed_images.push(ImageChange { old, new: new_index, fields });
+                }
+            }
+        }
+
+        for (new_bitmap, is_matched) in new_matched.into_iter().enumerate() {
+            if !is_matched {
+                diff.bitmaps.push(BitmapChange::Added { new: new_bitmap, name: new.bitmaps[new_bitmap].external_filename.clone() });
+                diff.added_images.extend(new.bitmap_images(new_bitmap));
+            }
+        }
+
+        diff.added_images.sort_unstable();
+        diff.removed_images.sort_unstable();
+
+        diff
+    }
+
+    /// Compare the metadata with the metadata of another (newer) file, along with the decoded pixels of the images of both files.
+    ///
+    /// Pixels are given in the order of the images, as returned by [SgFileMetadata::load_fully] with [VecImageBuilderFactory](crate::VecImageBuilderFactory).
+    pub fn diff_with_pixels(&self, old_pixels: &[Vec<u8>], new: &SgFileMetadata, new_pixels: &[Vec<u8>]) -> SgFileDiff {
+        let mut diff = self.diff(new);
+
+        for &(old, new_index) in &diff.matched_images {
+            let old_image = &self.images[old];
+            let new_image = &new.images[new_index];
+            let same_size = old_image.width == new_image.width && old_image.height == new_image.height;
+
+            if !same_size || old_pixels.get(old) != new_pixels.get(new_index) {
+                diff.changed_pixels.push((old, new_index));
+            }
+        }
+
+        diff
+    }
+
+    /// Get indexes of the image records belonging to the given bitmap.
+    fn bitmap_images(&self, bitmap: usize) -> Vec<usize> {
+        (1..self.raw_images.len()).filter(|&i| self.raw_images[i].bitmap_id as usize == bitmap).collect()
+    }
+
+    fn diff_fields(old: &SgImageMetadata, new: &SgImageMetadata) -> Vec<FieldChange> {
+        let mut fields = Vec::new();
+
+        let mut compare = |field: &'static str, old: &dyn Debug, new: &dyn Debug| {
+            let (old, new) = (format!("{:?}", old), format!("{:?}", new));
+            if old != new {
+                fields.push(FieldChange { field, old, new });
+            }
+        };
+
+        compare("offset", &old.offset, &new.offset);
+        compare("length", &old.length, &new.length);
+        compare("uncompressed_length", &old.uncompressed_length, &new.uncompressed_length);
+        compare("zeroes", &old.zeroes, &new.zeroes);
+        compare("invert_offset", &old.invert_offset, &new.invert_offset);
+        compare("width", &old.width, &new.width);
+        compare("height", &old.height, &new.height);
+        compare("unknown_a", &old.unknown_a, &new.unknown_a);
+        compare("anim_sprites", &old.anim_sprites, &new.anim_sprites);
+        compare("unknown_b", &old.unknown_b, &new.unknown_b);
+        compare("x_offset", &old.x_offset, &new.x_offset);
+        compare("y_offset", &old.y_offset, &new.y_offset);
+        compare("unknown_c", &old.unknown_c, &new.unknown_c);
+        compare("is_reversible", &old.is_reversible, &new.is_reversible);
+        compare("unknown_d", &old.unknown_d, &new.unknown_d);
+        compare("image_type", &old.image_type, &new.image_type);
+        compare("flags", &old.flags, &new.flags);
+        compare("bitmap_id", &old.bitmap_id, &new.bitmap_id);
+        compare("unknown_e", &old.unknown_e, &new.unknown_e);
+        compare("anim_speed_id", &old.anim_speed_id, &new.anim_speed_id);
+        compare("unknown_f", &old.unknown_f, &new.unknown_f);
+        compare("alpha_offset", &old.alpha_offset, &new.alpha_offset);
+        compare("alpha_length", &old.alpha_length, &new.alpha_length);
+
+        fields
+    }
+}
+
+#[cfg(feature = "image")]
+impl SgFileDiff {
+    /// Build an image showing the old and the new pixels of an image side by side, followed by a mask of the pixels that differ.
+    ///
+    /// Pixels that differ are red in the mask, pixels that are the same are a faded copy of the new pixels.
+    ///
+    /// Requires the `image` feature.
+    pub fn visual_diff(old_image: &SgImageMetadata, old_pixels: &[u8], new_image: &SgImageMetadata, new_pixels: &[u8]) -> image::RgbaImage {
+        let width = old_image.width.max(new_image.width) as u32;
+        let height = old_image.height.max(new_image.height) as u32;
+        let mut diff_image = image::RgbaImage::new(width * 3, height);
+
+        let pixel = |image: &SgImageMetadata, pixels: &[u8], x: u32, y: u32| {
+            if x >= image.width as u32 || y >= image.height as u32 {
+                return None;
+            }
+            let i = (y as usize * image.width as usize + x as usize) * 4;
+            pixels.get(i..(i + 4)).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let old_pixel = pixel(old_image, old_pixels, x, y);
+                let new_pixel = pixel(new_image, new_pixels, x, y);
+
+                diff_image.put_pixel(x, y, image::Rgba(old_pixel.unwrap_or_default()));
+                diff_image.put_pixel(width + x, y, image::Rgba(new_pixel.unwrap_or_default()));
+
+                let mask = match new_pixel {
+                    _ if old_pixel != new_pixel => [255, 0, 0, 255],
+                    Some([r, g, b, a]) => [r / 4, g / 4, b / 4, a],
+                    None => [0; 4],
+                };
+                diff_image.put_pixel(2 * width + x, y, image::Rgba(mask));
+            }
+        }
+
+        diff_image
+    }
+}
@@ -1,35 +1,74 @@
-use std::fmt::{Display, Formatter};
+use alloc::boxed::Box;
+use core::fmt::{Display, Formatter};
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
 use std::io::Error;
-use std::str::Utf8Error;
 
-pub type Result<T> = std::result::Result<T, SgImageError>;
+pub type Result<T> = core::result::Result<T, SgImageError>;
 
 #[derive(Debug)]
 pub enum SgImageError {
     InvalidHeader,
+    /// The version stored in the header is not one of the known versions, see [FormatVersion](crate::FormatVersion).
+    UnsupportedVersion(u32),
     ImageDataLengthMismatch,
     UnknownImageType(u16),
+    UnexpectedEof,
+    /// No image has the given index.
+    InvalidImageIndex(usize),
+    /// No bitmap has the given id.
+    InvalidBitmapId(usize),
+    #[cfg(feature = "std")]
     IoError(Error),
     Utf8Error(Utf8Error),
+    #[cfg(feature = "image")]
+    ImageError(image::ImageError),
+    #[cfg(feature = "import")]
+    InvalidManifest(String),
+    /// Error returned by a [TryImageBuilderFactory](crate::TryImageBuilderFactory) or [TryImageBuilder](crate::TryImageBuilder).
+    BuilderError(Box<dyn core::error::Error + Send + Sync>),
 }
 
 impl Display for SgImageError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             SgImageError::InvalidHeader => write!(f, "invalid header enountered"),
+            SgImageError::UnsupportedVersion(version) => write!(f, "unsupported sg file version: {:#x}", version),
             SgImageError::ImageDataLengthMismatch => write!(f, "data length mismatch detected"),
             SgImageError::UnknownImageType(_) => write!(f, "unknown image type encountered"),
+            SgImageError::UnexpectedEof => write!(f, "unexpected end of data"),
+            SgImageError::InvalidImageIndex(index) => write!(f, "invalid image index: {}", index),
+            SgImageError::InvalidBitmapId(bitmap_id) => write!(f, "invalid bitmap id: {}", bitmap_id),
+            #[cfg(feature = "std")]
             SgImageError::IoError(err) => write!(f, "IO error enountered: {}", err),
             SgImageError::Utf8Error(_) => write!(f, "error enountered when reading UTF8 srting"),
+            #[cfg(feature = "image")]
+            SgImageError::ImageError(err) => write!(f, "image error encountered: {}", err),
+            #[cfg(feature = "import")]
+            SgImageError::InvalidManifest(message) => write!(f, "invalid import manifest: {}", message),
+            SgImageError::BuilderError(err) => write!(f, "image builder error encountered: {}", err),
         }
-        
     }
 }
 
+#[cfg(feature = "std")]
 impl From<Error> for SgImageError {
     fn from(value: Error) -> Self {
         SgImageError::IoError(value)
     }
 }
 
-impl std::error::Error for SgImageError {}
+#[cfg(feature = "image")]
+impl From<image::ImageError> for SgImageError {
+    fn from(value: image::ImageError) -> Self {
+        SgImageError::ImageError(value)
+    }
+}
+
+impl SgImageError {
+    pub(crate) fn builder_error<E: core::error::Error + Send + Sync + 'static>(err: E) -> Self {
+        SgImageError::BuilderError(Box::new(err))
+    }
+}
+
+impl core::error::Error for SgImageError {}
@@ -0,0 +1,118 @@
+use crate::SgFileMetadata;
+
+/// Version of the sg file format, stored in the header of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatVersion {
+    /// SG2 files (version 0xd3), with room for 100 bitmap records.
+    Sg2,
+    /// SG3 files (version 0xd5), with room for 200 bitmap records.
+    Sg3,
+    /// SG3 files with alpha masks (version 0xd6), adding alpha offset and length to the image records.
+    Sg3Alpha,
+}
+
+impl FormatVersion {
+    /// Get the format of the given version number, if it is a known one.
+    pub fn from_version(version: u32) -> Option<Self> {
+        match version {
+            0xd3 => Some(FormatVersion::Sg2),
+            0xd5 => Some(FormatVersion::Sg3),
+            0xd6 => Some(FormatVersion::Sg3Alpha),
+            _ => None,
+        }
+    }
+
+    /// Get the version number stored in the header of the file.
+    pub fn version(&self) -> u32 {
+        match self {
+            FormatVersion::Sg2 => 0xd3,
+            FormatVersion::Sg3 => 0xd5,
+            FormatVersion::Sg3Alpha => 0xd6,
+        }
+    }
+
+    /// Number of bitmap records in the file, including unused ones.
+    pub fn max_bitmap_records(&self) -> u32 {
+        match self {
+            FormatVersion::Sg2 => 100,
+            FormatVersion::Sg3 | FormatVersion::Sg3Alpha => 200,
+        }
+    }
+
+    /// Size of an image record in bytes.
+    pub fn image_record_size(&self) -> usize {
+        if self.has_alpha() {
+            72
+        } else {
+            64
+        }
+    }
+
+    /// Checks if images can have alpha masks.
+    pub fn has_alpha(&self) -> bool {
+        *self == FormatVersion::Sg3Alpha
+    }
+}
+
+/// Game a sg file comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Game {
+    Caesar3,
+    /// Pharaoh and its Cleopatra expansion.
+    Pharaoh,
+    /// Zeus and its Poseidon expansion.
+    Zeus,
+    Emperor,
+    /// The game could not be told from the file.
+    Unknown,
+}
+
+/// Sizes stated in the header of the SG2 files of Caesar 3, the enemy files being the larger ones.
+const CAESAR3_SG2_FILE_SIZES: [u32; 2] = [74480, 522680];
+
+/// Parts of file names specific to each game, checked in order.
+const GAME_NAMES: [(&str, Game); 6] = [
+    ("emperor", Game::Emperor),
+    ("poseidon", Game::Zeus),
+    ("zeus", Game::Zeus),
+    ("cleopatra", Game::Pharaoh),
+    ("pharaoh", Game::Pharaoh),
+    ("c3", Game::Caesar3),
+];
+
+impl SgFileMetadata {
+    /// Get the format of the file, if its version is a known one.
+    pub fn format_version(&self) -> Option<FormatVersion> {
+        FormatVersion::from_version(self.version)
+    }
+
+    /// Detect the game the file comes from.
+    ///
+    /// SG2 files stating one of the sizes used by Caesar 3 (for the main and the enemy files) in the header come from it.
+    /// Otherwise names of the file and of the bitmaps are checked for names of the games.
+    pub fn game(&self) -> Game {
+        if self.format_version() == Some(FormatVersion::Sg2) && CAESAR3_SG2_FILE_SIZES.contains(&self.file_size) {
+            return Game::Caesar3;
+        }
+
+        let names = core::iter::once(&self.filename).chain(self.bitmaps.iter().map(|bitmap| &bitmap.external_filename));
+
+        for name in names {
+            let name = name.to_lowercase();
+            if let Some((_, game)) = GAME_NAMES.iter().find(|(game_name, _)| Self::name_matches(&name, game_name)) {
+                return *game;
+            }
+        }
+
+        Game::Unknown
+    }
+
+    /// Checks if the name contains the given game name, which is only matched at the start of the name if it is short.
+    fn name_matches(name: &str, game_name: &str) -> bool {
+        if game_name.len() <= 2 {
+            name.starts_with(game_name)
+        } else {
+            name.contains(game_name)
+        }
+    }
+}
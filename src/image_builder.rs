@@ -1,3 +1,10 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::convert::Infallible;
+
+use crate::{LoadOptions, SgBitmapMetadata, SgImageMetadata, TransparencyMode};
+
 /// A trait for providing [ImageBuilder] for a new image.
 ///
 /// Gets called internally to create a new builder for each encountered image.
@@ -9,6 +16,15 @@ pub trait ImageBuilderFactory<T> {
 
     /// Create a new builder for an image of the provided size
     fn new_builder(&self, width: u16, height: u16) -> Self::Builder;
+
+    /// Create a new builder for the given image, along with the bitmap it belongs to when loading images of a whole file.
+    ///
+    /// Allows handling images differently based on their metadata (e.g. type, offsets or mirrored state).
+    /// Defaults to [ImageBuilderFactory::new_builder] with the size of the image.
+    fn new_builder_for_image(&self, image: &SgImageMetadata, bitmap: Option<&SgBitmapMetadata>) -> Self::Builder {
+        let _ = bitmap;
+        self.new_builder(image.width, image.height)
+    }
 }
 
 /// A trait for building an image from provided pixels.
@@ -30,6 +46,92 @@ pub trait ImageBuilder<T> {
     fn build(self) -> T;
 }
 
+/// A fallible version of [ImageBuilderFactory], for factories that may fail to create a builder (e.g. when hitting size limits).
+///
+/// Implemented for all the [ImageBuilderFactory] implementations, with [Infallible] as the error.
+/// Errors are returned from the loading functions as [SgImageError::BuilderError](crate::SgImageError::BuilderError).
+pub trait TryImageBuilderFactory<T> {
+    /// The type of the corresponding builder
+    type Builder: TryImageBuilder<T>;
+
+    /// The type of the error returned when a builder cannot be created
+    type Error: core::error::Error + Send + Sync + 'static;
+
+    /// Create a new builder for the given image, along with the bitmap it belongs to when loading images of a whole file.
+    fn try_new_builder_for_image(&self, image: &SgImageMetadata, bitmap: Option<&SgBitmapMetadata>) -> Result<Self::Builder, Self::Error>;
+}
+
+/// A fallible version of [ImageBuilder], for builders that may fail to build the image (e.g. when writing it to disk).
+///
+/// Implemented for all the [ImageBuilder] implementations, with [Infallible] as the error.
+/// The methods are prefixed with `try_` so that they don't clash with the [ImageBuilder] ones when both traits are in scope.
+pub trait TryImageBuilder<T> {
+    /// The type of the error returned when the image cannot be built
+    type Error: core::error::Error + Send + Sync + 'static;
+
+    /// Set the specified pixel to the given colour given as RGBA
+    fn try_set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]);
+
+    /// Set alpha on the specified pixel
+    fn try_set_alpha(&mut self, position: usize, alpha: u8);
+
+    /// Mirror each pixel horizontally
+    fn try_flip_horizontal(&mut self);
+
+    /// Consume the builder and return the resulting image
+    fn try_build(self) -> Result<T, Self::Error>;
+}
+
+impl<T, F: ImageBuilderFactory<T>> TryImageBuilderFactory<T> for F {
+    type Builder = F::Builder;
+    type Error = Infallible;
+
+    fn try_new_builder_for_image(&self, image: &SgImageMetadata, bitmap: Option<&SgBitmapMetadata>) -> Result<Self::Builder, Self::Error> {
+        Ok(self.new_builder_for_image(image, bitmap))
+    }
+}
+
+impl<T, B: ImageBuilder<T>> TryImageBuilder<T> for B {
+    type Error = Infallible;
+
+    fn try_set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.set_pixel_by_pos(position, data);
+    }
+
+    fn try_set_alpha(&mut self, position: usize, alpha: u8) {
+        self.set_alpha(position, alpha);
+    }
+
+    fn try_flip_horizontal(&mut self) {
+        self.flip_horizontal();
+    }
+
+    fn try_build(self) -> Result<T, Self::Error> {
+        Ok(self.build())
+    }
+}
+
+/// Wraps a fallible builder, so that pixels can be written into it like into any other builder.
+pub(crate) struct FallibleBuilder<B>(pub(crate) B);
+
+impl<T, B: TryImageBuilder<T>> ImageBuilder<Result<T, B::Error>> for FallibleBuilder<B> {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.0.try_set_pixel_by_pos(position, data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        self.0.try_set_alpha(position, alpha);
+    }
+
+    fn flip_horizontal(&mut self) {
+        self.0.try_flip_horizontal();
+    }
+
+    fn build(self) -> Result<T, B::Error> {
+        self.0.try_build()
+    }
+}
+
 /// Default implementation of [ImageBuilderFactory] for creating images as vector of bytes.
 pub struct VecImageBuilderFactory;
 
@@ -82,14 +184,271 @@ impl ImageBuilder<Vec<u8>> for VecImageBuilder {
     }
 }
 
+/// Implementation of [TryImageBuilderFactory] for writing an image into a region of a caller-provided RGBA buffer.
+///
+/// The image is written with its top left corner at the given position, with consecutive rows `stride` bytes apart.
+/// The region is cleared before any pixels get written to it.
+///
+/// As the factory holds onto the buffer, it can only create a builder for a single image.
+pub struct RegionImageBuilderFactory<'a> {
+    buffer: Cell<Option<&'a mut [u8]>>,
+    stride: usize,
+    x: usize,
+    y: usize,
+}
+
+/// Error returned by [RegionImageBuilderFactory] when it cannot create a builder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionImageError {
+    /// The buffer was already used by a builder created earlier.
+    BufferAlreadyUsed,
+    /// The image placed at the position of the region does not fit in a row of the buffer.
+    RowTooShort,
+    /// The image placed at the position of the region goes past the end of the buffer.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for RegionImageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RegionImageError::BufferAlreadyUsed => write!(f, "buffer already used by another builder"),
+            RegionImageError::RowTooShort => write!(f, "image does not fit in a row of the buffer"),
+            RegionImageError::BufferTooSmall => write!(f, "image does not fit in the buffer"),
+        }
+    }
+}
+
+impl core::error::Error for RegionImageError {}
+
+impl<'a> RegionImageBuilderFactory<'a> {
+    /// Create a factory writing to the given buffer with rows `stride` bytes apart, placing the image at (`x`, `y`).
+    pub fn new(buffer: &'a mut [u8], stride: usize, x: usize, y: usize) -> Self {
+        let buffer = Cell::new(Some(buffer));
+        RegionImageBuilderFactory { buffer, stride, x, y }
+    }
+
+    /// Get range of the buffer covered by an image of the given size, checking that it fits.
+    fn region(&self, buffer_length: usize, width: usize, height: usize) -> Result<core::ops::Range<usize>, RegionImageError> {
+        let row_end = self.x.checked_add(width).and_then(|end| end.checked_mul(4));
+        if row_end.is_none_or(|row_end| row_end > self.stride) {
+            return Err(RegionImageError::RowTooShort);
+        }
+
+        let start = self.x.checked_mul(4).and_then(|column| self.y.checked_mul(self.stride)?.checked_add(column));
+        let last_row = height.checked_sub(1).and_then(|rows| rows.checked_mul(self.stride));
+        let end = start.and_then(|start| start.checked_add(last_row?)?.checked_add(width.checked_mul(4)?));
+
+        match (start, end) {
+            (Some(start), Some(end)) if end <= buffer_length => Ok(start..end),
+            _ => Err(RegionImageError::BufferTooSmall),
+        }
+    }
+}
+
+impl<'a> TryImageBuilderFactory<()> for RegionImageBuilderFactory<'a> {
+    type Builder = RegionImageBuilder<'a>;
+    type Error = RegionImageError;
+
+    /// Create a builder writing to the region of the buffer.
+    ///
+    /// Fails if a builder was already created by this factory or if the image does not fit in the buffer.
+    fn try_new_builder_for_image(&self, image: &SgImageMetadata, _bitmap: Option<&SgBitmapMetadata>) -> Result<Self::Builder, Self::Error> {
+        let buffer = self.buffer.take().ok_or(RegionImageError::BufferAlreadyUsed)?;
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let stride = self.stride;
+
+        if width == 0 || height == 0 {
+            return Ok(RegionImageBuilder { pixels: &mut [], width, height, stride });
+        }
+
+        let region = match self.region(buffer.len(), width, height) {
+            Ok(region) => region,
+            Err(err) => {
+                // Keep the buffer for a builder of an image that fits
+                self.buffer.set(Some(buffer));
+                return Err(err);
+            }
+        };
+
+        let pixels = &mut buffer[region];
+
+        for row in pixels.chunks_mut(stride) {
+            row[..(width * 4)].fill(0);
+        }
+
+        Ok(RegionImageBuilder { pixels, width, height, stride })
+    }
+}
+
+/// Implementation of [ImageBuilder] for writing an image into a region of a caller-provided RGBA buffer.
+///
+/// See [RegionImageBuilderFactory].
+pub struct RegionImageBuilder<'a> {
+    pixels: &'a mut [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+}
+
+impl<'a> RegionImageBuilder<'a> {
+    fn index(&self, position: usize) -> usize {
+        (position / self.width) * self.stride + (position % self.width) * 4
+    }
+}
+
+impl<'a> ImageBuilder<()> for RegionImageBuilder<'a> {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        let i = self.index(position);
+        self.pixels[i..(i + 4)].clone_from_slice(&data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        let i = self.index(position) + 3;
+        self.pixels[i] = alpha;
+    }
+
+    fn flip_horizontal(&mut self) {
+        let mut row_offset = 0;
+        for _y in 0..self.height {
+            for x in 0..(self.width / 2) {
+                let pixel_a = row_offset + x * 4;
+                let pixel_b = row_offset + (self.width - x - 1) * 4;
+                self.pixels.swap(pixel_a, pixel_b);
+                self.pixels.swap(pixel_a + 1, pixel_b + 1);
+                self.pixels.swap(pixel_a + 2, pixel_b + 2);
+                self.pixels.swap(pixel_a + 3, pixel_b + 3);
+            }
+            row_offset += self.stride;
+        }
+    }
+
+    fn build(self) {}
+}
+
+/// Factory for recording the pixels of an image, so they can be replayed into other builders.
+#[cfg(feature = "std")]
+pub(crate) struct RecordingImageBuilderFactory;
+
+#[cfg(feature = "std")]
+impl ImageBuilderFactory<RecordedImage> for RecordingImageBuilderFactory {
+    type Builder = RecordedImage;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        let width = width as usize;
+        let pixels = vec![None; width * height as usize];
+        RecordedImage { width, pixels }
+    }
+}
+
+/// Pixels of an image, keeping track of which of them were written.
+#[cfg(feature = "std")]
+pub(crate) struct RecordedImage {
+    width: usize,
+    pixels: Vec<Option<[u8; 4]>>,
+}
+
+#[cfg(feature = "std")]
+impl RecordedImage {
+    /// Write the recorded pixels into a new builder for the given image, optionally mirroring the image, and build it.
+    pub(crate) fn replay<T, F: TryImageBuilderFactory<T>>(
+        &self,
+        image_builder_factory: &F,
+        image: &SgImageMetadata,
+        bitmap: Option<&SgBitmapMetadata>,
+        flip_horizontal: bool,
+    ) -> crate::Result<T> {
+        let image_builder = image_builder_factory.try_new_builder_for_image(image, bitmap).map_err(crate::SgImageError::builder_error)?;
+        let mut image_builder = FallibleBuilder(image_builder);
+
+        for (position, pixel) in self.pixels.iter().enumerate() {
+            if let Some(data) = pixel {
+                image_builder.set_pixel_by_pos(position, *data);
+            }
+        }
+
+        if flip_horizontal {
+            image_builder.flip_horizontal();
+        }
+
+        image_builder.build().map_err(crate::SgImageError::builder_error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ImageBuilder<RecordedImage> for RecordedImage {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.pixels[position] = Some(data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        self.pixels[position].get_or_insert([0; 4])[3] = alpha;
+    }
+
+    fn flip_horizontal(&mut self) {
+        if self.width > 0 {
+            for row in self.pixels.chunks_mut(self.width) {
+                row.reverse();
+            }
+        }
+    }
+
+    fn build(self) -> RecordedImage {
+        self
+    }
+}
+
+/// Wraps a builder to premultiply colours by the alpha set after them.
+///
+/// Keeps track of the colours written so far, as alpha masks are applied after all the colours have been set.
+pub(crate) struct PremultipliedAlphaBuilder<B> {
+    builder: B,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl<B> PremultipliedAlphaBuilder<B> {
+    pub(crate) fn new(builder: B, width: u16, height: u16) -> Self {
+        let pixels = vec![[0; 4]; width as usize * height as usize];
+        PremultipliedAlphaBuilder { builder, pixels }
+    }
+}
+
+impl<T, B: ImageBuilder<T>> ImageBuilder<T> for PremultipliedAlphaBuilder<B> {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.pixels[position] = data;
+        self.builder.set_pixel_by_pos(position, data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        let [r, g, b, _] = self.pixels[position];
+        let premultiply = |channel: u8| ((channel as u16 * alpha as u16 + 127) / 255) as u8;
+        self.builder.set_pixel_by_pos(position, [premultiply(r), premultiply(g), premultiply(b), alpha]);
+    }
+
+    fn flip_horizontal(&mut self) {
+        self.builder.flip_horizontal();
+    }
+
+    fn build(self) -> T {
+        self.builder.build()
+    }
+}
+
 pub(crate) trait ImageBuilderHelper<T> {
-    fn set_555_pixel_by_pos(&mut self, position: usize, colour: u16);
+    fn set_555_pixel_by_pos(&mut self, position: usize, colour: u16, options: &LoadOptions);
 }
 
 impl<T, B: ImageBuilder<T>> ImageBuilderHelper<T> for B {
-    fn set_555_pixel_by_pos(&mut self, position: usize, colour: u16) {
-        if colour == 0xf81f {
-            return;
+    fn set_555_pixel_by_pos(&mut self, position: usize, colour: u16, options: &LoadOptions) {
+        if options.is_transparent(colour) {
+            match options.transparency_mode {
+                TransparencyMode::Skip => return,
+                TransparencyMode::TransparentBlack => {
+                    self.set_pixel_by_pos(position, [0, 0, 0, 0]);
+                    return;
+                }
+                TransparencyMode::Opaque => {}
+            }
         }
 
         let ones = 0xf8_u8;
@@ -0,0 +1,101 @@
+use crate::SgFileMetadata;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+/// Number of image groups in the header of a sg file.
+pub(crate) const GROUP_COUNT: usize = 300;
+
+/// Names of image groups, as used by reimplementations of the games (Julius, Augustus, Akhenaten etc.).
+///
+/// Images are referenced there by a group id and an offset within the group,
+/// with group ids given names in C headers like `#define GROUP_TERRAIN_GRASS_1 61`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GroupNames {
+    groups: BTreeMap<String, u16>,
+    /// Name of each group inserted first, which is the one defined first in a header.
+    names: BTreeMap<u16, String>,
+}
+
+impl GroupNames {
+    /// Parse group names from the `#define <NAME> <GROUP_ID>` lines of a C header, other lines are ignored.
+    pub fn parse_c_header(source: &str) -> Self {
+        let mut group_names = GroupNames::default();
+
+        for line in source.lines() {
+            let mut tokens = line.split_whitespace();
+            if tokens.next() != Some("#define") {
+                continue;
+            }
+
+            if let (Some(name), Some(Ok(group))) = (tokens.next(), tokens.next().map(str::parse)) {
+                group_names.insert(name, group);
+            }
+        }
+
+        group_names
+    }
+
+    /// Add a name for the given group.
+    pub fn insert(&mut self, name: &str, group: u16) {
+        self.groups.insert(String::from(name), group);
+        self.names.entry(group).or_insert_with(|| String::from(name));
+    }
+
+    /// Get id of the group with the given name.
+    pub fn group(&self, name: &str) -> Option<u16> {
+        self.groups.get(name).copied()
+    }
+
+    /// Get name of the group with the given id.
+    ///
+    /// Groups with several names (aliases) get the name inserted first, which is the one defined first when parsed from a header.
+    pub fn name(&self, group: u16) -> Option<&str> {
+        self.names.get(&group).map(String::as_str)
+    }
+}
+
+impl SgFileMetadata {
+    /// Get index of the image at the given offset within the given image group.
+    ///
+    /// Returns `None` if there is no such group or image.
+    pub fn group_image_index(&self, group: u16, offset: u16) -> Option<usize> {
+        let first_image = *self.group_image_ids.get(group as usize)? as usize;
+        let index = first_image + offset as usize;
+
+        if index < self.images.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Get index of the image at the given offset within the image group with the given name.
+    pub fn named_group_image_index(&self, group_names: &GroupNames, name: &str, offset: u16) -> Option<usize> {
+        self.group_image_index(group_names.group(name)?, offset)
+    }
+
+    /// Get the image group and the offset within it of the image with the given index.
+    ///
+    /// The image belongs to the group starting closest before it, the lowest group id is used when several groups start at the same image.
+    pub fn image_group(&self, index: usize) -> Option<(u16, u16)> {
+        if index >= self.images.len() {
+            return None;
+        }
+
+        let mut result: Option<(u16, u16)> = None;
+
+        for (group, first_image) in self.group_image_ids.iter().enumerate() {
+            let first_image = *first_image as usize;
+            if first_image > index {
+                continue;
+            }
+
+            let offset = (index - first_image) as u16;
+            if result.is_none_or(|(_, best_offset)| offset < best_offset) {
+                result = Some((group as u16, offset));
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::image_groups::GROUP_COUNT;
+use crate::{FormatVersion, Result, SgBitmapMetadata, SgFileMetadata, SgImageError, SgImageMetadata};
+
+/// Description of a sg file to be built from PNG files, see [SgFileMetadata::import].
+///
+/// Can be read from JSON, with any field left out taking its default value.
+///
+/// Requires the `import` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportManifest {
+    /// Version of the sg file, alpha masks are only written for version 0xd6.
+    pub version: u32,
+    /// Index of the first image of each image group.
+    pub group_image_ids: Vec<u16>,
+    pub bitmaps: Vec<ImportBitmap>,
+}
+
+/// Description of a bitmap of an imported sg file.
+///
+/// Requires the `import` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportBitmap {
+    pub external_filename: String,
+    pub comment: String,
+    pub images: Vec<ImportImage>,
+}
+
+/// Description of an image of an imported sg file.
+///
+/// Requires the `import` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportImage {
+    /// Path to the PNG file with pixels of the image, relative to the imported folder. The image is empty if not given.
+    pub file: Option<String>,
+    pub image_type: u16,
+    pub x_offset: u16,
+    pub y_offset: u16,
+    pub anim_sprites: u16,
+    pub anim_speed_id: u8,
+    pub is_reversible: bool,
+    /// Size of the isometric footprint in tiles, derived from the image dimensions if not given.
+    pub isometric_size: Option<u8>,
+    /// Index of the image (counting from 1, across all bitmaps) this image is a mirrored copy of, in place of a PNG file.
+    ///
+    /// Only the pixel data, size and flags are taken from the source, other fields come from this image.
+    pub mirror_of: Option<usize>,
+}
+
+impl Default for ImportManifest {
+    fn default() -> Self {
+        ImportManifest {
+            version: 0xd5,
+            group_image_ids: Vec::new(),
+            bitmaps: Vec::new(),
+        }
+    }
+}
+
+impl ImportManifest {
+    /// Parse the manifest from JSON.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| SgImageError::InvalidManifest(err.to_string()))
+    }
+
+    /// Load the manifest from the JSON file found on the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl SgFileMetadata {
+    /// Build a sg file and the .555 file with its pixel data from the PNG files in the given folder, as described by the manifest.
+    ///
+    /// The sg file is written to the given path, along with the .555 file named after it, which holds pixel data of all the images.
+    /// Returns metadata of the written file.
+    ///
+    /// Requires the `import` feature.
+    pub fn import<P: AsRef<Path>, Q: AsRef<Path>>(manifest: &ImportManifest, png_folder: P, sg_path: Q) -> Result<Self> {
+        let format_version = FormatVersion::from_version(manifest.version).ok_or(SgImageError::UnsupportedVersion(manifest.version))?;
+        let include_alpha = format_version.has_alpha();
+
+        if manifest.bitmaps.len() > format_version.max_bitmap_records() as usize {
+            return Err(SgImageError::InvalidManifest(format!("too many bitmaps: {}", manifest.bitmaps.len())));
+        }
+
+        let mut data_555 = Vec::new();
+        let mut bitmaps = Vec::with_capacity(manifest.bitmaps.len());
+        let mut raw_images = vec![SgImageMetadata::default()];
+
+        for (bitmap_id, import_bitmap) in manifest.bitmaps.iter().enumerate() {
+            let start_index = raw_images.len();
+            let data_start = data_555.len();
+
+            for import_image in &import_bitmap.images {
+                let id = raw_images.len() as u32;
+                let mut image = SgImageMetadata {
+                    id,
+                    image_type: import_image.image_type,
+                    x_offset: import_image.x_offset,
+                    y_offset: import_image.y_offset,
+                    anim_sprites: import_image.anim_sprites,
+                    anim_speed_id: import_image.anim_speed_id,
+                    is_reversible: import_image.is_reversible as u8,
+                    bitmap_id: bitmap_id as u8,
+                    ..SgImageMetadata::default()
+                };
+                image.flags[3] = import_image.isometric_size.unwrap_or(0);
+
+                if let Some(source) = import_image.mirror_of {
+                    let source_image = raw_images.get(source).filter(|_| source > 0).ok_or_else(|| {
+                        SgImageError::InvalidManifest(format!("image {} mirrors image {} which is not defined before it", id, source))
+                    })?;
+
+                    // Mirrored copies keep their own record, sharing only the pixel data of the source
+                    image.invert_offset = source as i32 - id as i32;
+                    image.offset = source_image.offset;
+                    image.length = source_image.length;
+                    image.uncompressed_length = source_image.uncompressed_length;
+                    image.alpha_offset = source_image.alpha_offset;
+                    image.alpha_length = source_image.alpha_length;
+                    image.width = source_image.width;
+                    image.height = source_image.height;
+                    image.flags = source_image.flags;
+                } else if let Some(file) = &import_image.file {
+                    let pixels = image::open(png_folder.as_ref().join(file))?.to_rgba8();
+
+                    if pixels.width() > u16::MAX as u32 || pixels.height() > u16::MAX as u32 {
+                        return Err(SgImageError::InvalidManifest(format!("image {} is too large", file)));
+                    }
+
+                    image.width = pixels.width() as u16;
+                    image.height = pixels.height() as u16;
+
+                    let data = image.encode_image(pixels.as_raw(), include_alpha)?;
+                    image.offset = data_555.len() as u32;
+                    if image.alpha_length > 0 {
+                        image.alpha_offset = image.offset + image.length;
+                    }
+                    data_555.extend_from_slice(&data);
+                }
+
+                raw_images.push(image);
+            }
+
+            let bitmap_data_length = (data_555.len() - data_start) as u32;
+
+            bitmaps.push(SgBitmapMetadata {
+                id: bitmap_id as u32,
+                external_filename: import_bitmap.external_filename.clone(),
+                comment: import_bitmap.comment.clone(),
+                num_images: (raw_images.len() - start_index) as u32,
+                start_index: start_index as u32,
+                end_index: (raw_images.len() - 1) as u32,
+                file_size_555: bitmap_data_length,
+                total_file_size: bitmap_data_length,
+                ..SgBitmapMetadata::default()
+            });
+        }
+
+        let mut group_image_ids = manifest.group_image_ids.clone();
+        group_image_ids.resize(GROUP_COUNT, 0);
+
+        let sg_path = sg_path.as_ref();
+        let folder = sg_path.parent().and_then(Path::to_str).unwrap_or_default();
+        let filename = sg_path.file_name().and_then(|filename| filename.to_str()).unwrap_or_default();
+
+        let mut sg_file = SgFileMetadata {
+            folder: String::from(folder),
+            filename: String::from(filename),
+            version: manifest.version,
+            max_image_count: raw_images.len() as u32,
+            bitmap_records_without_system: bitmaps.len() as u32,
+            total_file_size: data_555.len() as u32,
+            file_size_555: data_555.len() as u32,
+            group_image_ids,
+            bitmaps,
+            images: Self::resolve_images(&raw_images),
+            raw_images,
+            ..SgFileMetadata::default()
+        };
+
+        // SG2 files state a fixed size, SG3 files the actual size of the file
+        sg_file.file_size = if sg_file.version == 0xd3 { 74480 } else { sg_file.to_bytes().len() as u32 };
+
+        fs::write(sg_path, sg_file.to_bytes())?;
+        fs::write(sg_file.get_555_file_path(0, false), data_555)?;
+
+        Ok(sg_file)
+    }
+}
@@ -3,11 +3,14 @@
 //! Documentation of the format can be found at https://github.com/bvschaik/citybuilding-tools/wiki/SG-file-format#image-data.
 //!
 //! Simple usage:
-//! ```rust
+//! ```rust,no_run
 //! use sg_image_reader::{SgFileMetadata, VecImageBuilderFactory};
 //!
+//! # fn main() -> sg_image_reader::Result<()> {
 //! let path = "path-to-file";
 //! let (sg_file, pixel_data): (SgFileMetadata, Vec<Vec<u8>>) = SgFileMetadata::load_fully(path, &VecImageBuilderFactory)?;
+//! # Ok(())
+//! # }
 //! ```
 //!
 //! The basic example provides a vector of raw bytes for all the images.
@@ -15,13 +18,15 @@
 //! It is also possible to construct the required images directly by implementing the [`ImageBuilderFactory`] trait and passing it instead of the [`VecImageBuilderFactory`].
 //!
 //! Pixel data can also be loaded for one image at a time, see `viewer` example for an example of that
-//! ```rust
+//! ```rust,no_run
 //! use std::fs::File;
 //! use std::io::BufReader;
 //! use sg_image_reader::{SgFileMetadata, VecImageBuilderFactory};
 //!
+//! # fn main() -> sg_image_reader::Result<()> {
+//! # let path = "path-to-file";
 //! // Load just the metadata
-//! let sg_file = SgFileMetadata::load_metadata(path)?;
+//! let sg_file = SgFileMetadata::load_metadata_from_path(path)?;
 //!
 //! // Select the image we want to load pixel data for
 //! let image = &sg_file.images[11];
@@ -33,18 +38,56 @@
 //! let mut buf_reader = BufReader::new(File::open(path)?);
 //!
 //! // Load pixel data for that specific image
-//! let pixel_data = image.load_image(&mut buf_reader, &VecImageBuilderFactory);
+//! let pixel_data = image.load_image(&mut buf_reader, &VecImageBuilderFactory)?;
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! Without the default `std` feature the crate is `no_std` (requiring `alloc`).
+//! Metadata and pixel data can then be loaded from the contents of the files with [`SgFileMetadata::load_metadata_from_bytes`] and [`SgImageMetadata::load_image_from_bytes`].
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use diff::{BitmapChange, FieldChange, ImageChange, SgFileDiff};
 pub use error::{Result, SgImageError};
+pub use game::{FormatVersion, Game};
 pub use image_builder::*;
+pub use image_groups::GroupNames;
+#[cfg(feature = "import")]
+pub use import::{ImportBitmap, ImportImage, ImportManifest};
+pub use lint::{BitmapIndexField, DataSizeField, LintFinding};
+pub use load_options::{AlphaMode, LoadOptions, MirrorMode, TransparencyMode, DEFAULT_TRANSPARENT_COLOUR};
+#[cfg(feature = "image")]
+pub use rgba_image::{RgbaImageBuilder, RgbaImageBuilderFactory};
 pub use sg_bitmap::SgBitmapMetadata;
-pub use sg_file::SgFileMetadata;
+pub use sg_file::{SgFileMetadata, SgImageData};
 pub use sg_image::SgImageMetadata;
+pub use stats::{BitmapStats, ImageStats, SgFileStats};
+pub use text::TextEncoding;
 pub(crate) use utils::*;
 
+#[cfg(feature = "image")]
+mod asset_pack;
+#[cfg(feature = "async")]
+mod async_io;
+mod diff;
 mod error;
+mod game;
 mod image_builder;
+mod image_groups;
+#[cfg(feature = "import")]
+mod import;
+mod lint;
+mod load_options;
+#[cfg(feature = "image")]
+mod rgba_image;
 mod sg_bitmap;
 mod sg_file;
 mod sg_image;
+mod sg_writer;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+mod text;
 mod utils;
@@ -0,0 +1,209 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use crate::{SgFileMetadata, SgImageMetadata};
+
+/// Field of a bitmap record describing which images belong to the bitmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapIndexField {
+    StartIndex,
+    EndIndex,
+    NumImages,
+}
+
+/// Field of the sg file or a bitmap record stating the size of pixel data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSizeField {
+    FileSize555,
+    FileSizeExternal,
+}
+
+/// Inconsistency found in the metadata of a sg file, see [SgFileMetadata::lint].
+///
+/// Images are referred to by their index in [SgFileMetadata::raw_images].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintFinding {
+    /// The image belongs to a bitmap that does not exist.
+    InvalidBitmapId { image: usize, bitmap_id: u8 },
+    /// The bitmap record disagrees with the images belonging to the bitmap.
+    BitmapIndexMismatch { bitmap: usize, field: BitmapIndexField, stated: u32, actual: u32 },
+    /// The pixel data of the image ends past the end of the data file, as stated by its size in the metadata.
+    DataOutOfBounds { image: usize, end: u64, size: u64 },
+    /// The pixel data of the images partially overlaps, images with the exact same data are not reported.
+    OverlappingData { image: usize, other: usize },
+    /// The image is mirrored from an image that does not exist or comes after it.
+    InvalidMirrorSource { image: usize, invert_offset: i32 },
+    /// The size of pixel data stated in the header (or in the bitmap record if `bitmap` is given) differs from the size of the image data.
+    DataSizeMismatch { bitmap: Option<usize>, field: DataSizeField, stated: u32, actual: u64 },
+    /// There are more images than stated in the header.
+    TooManyImages { count: usize, max_image_count: u32 },
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LintFinding::InvalidBitmapId { image, bitmap_id } => write!(f, "image {} belongs to missing bitmap {}", image, bitmap_id),
+            LintFinding::BitmapIndexMismatch { bitmap, field, stated, actual } => {
+                write!(f, "bitmap {} states {:?} {}, images give {}", bitmap, field, stated, actual)
+            }
+            LintFinding::DataOutOfBounds { image, end, size } => write!(f, "data of image {} ends at {}, past the data file size {}", image, end, size),
+            LintFinding::OverlappingData { image, other } => write!(f, "data of image {} overlaps data of image {}", image, other),
+            LintFinding::InvalidMirrorSource { image, invert_offset } => write!(f, "image {} is mirrored from missing image at offset {}", image, invert_offset),
+            LintFinding::DataSizeMismatch { bitmap: Some(bitmap), field, stated, actual } => {
+                write!(f, "bitmap {} states {:?} {}, images give {}", bitmap, field, stated, actual)
+            }
+            LintFinding::DataSizeMismatch { bitmap: None, field, stated, actual } => write!(f, "header states {:?} {}, images give {}", field, stated, actual),
+            LintFinding::TooManyImages { count, max_image_count } => write!(f, "{} images, more than the maximum of {}", count, max_image_count),
+        }
+    }
+}
+
+impl SgFileMetadata {
+    /// Check the metadata for inconsistencies, without loading any pixel data.
+    ///
+    /// Returns an empty list for consistent metadata.
+    pub fn lint(&self) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+
+        self.lint_image_count(&mut findings);
+        self.lint_bitmap_ids(&mut findings);
+        self.lint_bitmap_indexes(&mut findings);
+        self.lint_mirror_sources(&mut findings);
+        self.lint_data_spans(&mut findings);
+        self.lint_data_sizes(&mut findings);
+
+        findings
+    }
+
+    fn lint_image_count(&self, findings: &mut Vec<LintFinding>) {
+        let count = self.raw_images.len().saturating_sub(1);
+        if count > self.max_image_count as usize {
+            findings.push(LintFinding::TooManyImages { count, max_image_count: self.max_image_count });
+        }
+    }
+
+    fn lint_bitmap_ids(&self, findings: &mut Vec<LintFinding>) {
+        for (image, raw_image) in self.raw_images.iter().enumerate().skip(1) {
+            if raw_image.bitmap_id as usize >= self.bitmaps.len() {
+                findings.push(LintFinding::InvalidBitmapId { image, bitmap_id: raw_image.bitmap_id });
+            }
+        }
+    }
+
+    fn lint_bitmap_indexes(&self, findings: &mut Vec<LintFinding>) {
+        for (bitmap, bitmap_metadata) in self.bitmaps.iter().enumerate() {
+            let indexes: Vec<usize> = (1..self.raw_images.len()).filter(|&i| self.raw_images[i].bitmap_id as usize == bitmap).collect();
+
+            let mut check = |field, stated: u32, actual: usize| {
+                if stated as usize != actual {
+                    findings.push(LintFinding::BitmapIndexMismatch { bitmap, field, stated, actual: actual as u32 });
+                }
+            };
+
+            check(BitmapIndexField::NumImages, bitmap_metadata.num_images, indexes.len());
+
+            if let (Some(first), Some(last)) = (indexes.first(), indexes.last()) {
+                check(BitmapIndexField::StartIndex, bitmap_metadata.start_index, *first);
+                check(BitmapIndexField::EndIndex, bitmap_metadata.end_index, *last);
+            }
+        }
+    }
+
+    fn lint_mirror_sources(&self, findings: &mut Vec<LintFinding>) {
+        for (image, raw_image) in self.raw_images.iter().enumerate() {
+            let is_valid = raw_image.mirror_source().is_none_or(|source| source > 0 && source < image);
+            if !is_valid {
+                findings.push(LintFinding::InvalidMirrorSource { image, invert_offset: raw_image.invert_offset });
+            }
+        }
+    }
+
+    fn lint_data_spans(&self, findings: &mut Vec<LintFinding>) {
+        // Spans of the images with their own pixel data, grouped by data file
+        let mut spans: BTreeMap<String, Vec<(u64, u64, usize)>> = BTreeMap::new();
+
+        for (image, raw_image) in self.raw_images.iter().enumerate() {
+            if !Self::has_own_data(raw_image) || raw_image.bitmap_id as usize >= self.bitmaps.len() {
+                continue;
+            }
+
+            let start = raw_image.data_position();
+            let end = start + raw_image.data_length() as u64;
+
+            let size = if raw_image.is_external() { self.bitmaps[raw_image.bitmap_id as usize].file_size_external } else { self.file_size_555 };
+            if size > 0 && end > size as u64 {
+                findings.push(LintFinding::DataOutOfBounds { image, end, size: size as u64 });
+            }
+
+            let file_name = self.get_555_file_name(raw_image.bitmap_id as usize, raw_image.is_external());
+            spans.entry(file_name).or_default().push((start, end, image));
+        }
+
+        for spans in spans.values_mut() {
+            spans.sort();
+
+            // Only the span reaching the furthest so far can overlap the next one
+            let mut furthest: Option<(u64, u64, usize)> = None;
+            for &(start, end, image) in spans.iter() {
+                if let Some((other_start, other_end, other)) = furthest {
+                    let is_shared = other_start == start && other_end == end;
+                    if start < other_end && !is_shared {
+                        findings.push(LintFinding::OverlappingData { image, other });
+                    }
+                }
+
+                if furthest.is_none_or(|(_, other_end, _)| end > other_end) {
+                    furthest = Some((start, end, image));
+                }
+            }
+        }
+    }
+
+    fn lint_data_sizes(&self, findings: &mut Vec<LintFinding>) {
+        let mut internal = 0;
+        let mut external = 0;
+        let mut bitmap_sizes = alloc::vec![(0, 0); self.bitmaps.len()];
+
+        for raw_image in &self.raw_images {
+            if !Self::has_own_data(raw_image) {
+                continue;
+            }
+
+            let length = raw_image.data_length() as u64;
+            let bitmap_size = bitmap_sizes.get_mut(raw_image.bitmap_id as usize);
+
+            if raw_image.is_external() {
+                external += length;
+                if let Some((_, bitmap_external)) = bitmap_size {
+                    *bitmap_external += length;
+                }
+            } else {
+                internal += length;
+                if let Some((bitmap_internal, _)) = bitmap_size {
+                    *bitmap_internal += length;
+                }
+            }
+        }
+
+        let mut check = |bitmap, field, stated: u32, actual: u64| {
+            if stated as u64 != actual {
+                findings.push(LintFinding::DataSizeMismatch { bitmap, field, stated, actual });
+            }
+        };
+
+        check(None, DataSizeField::FileSize555, self.file_size_555, internal);
+        check(None, DataSizeField::FileSizeExternal, self.file_size_external, external);
+
+        for (bitmap, (bitmap_metadata, (internal, external))) in self.bitmaps.iter().zip(bitmap_sizes).enumerate() {
+            check(Some(bitmap), DataSizeField::FileSize555, bitmap_metadata.file_size_555, internal);
+            check(Some(bitmap), DataSizeField::FileSizeExternal, bitmap_metadata.file_size_external, external);
+        }
+    }
+
+    /// Checks if the image record points at pixel data of its own, rather than mirroring another image.
+    fn has_own_data(raw_image: &SgImageMetadata) -> bool {
+        !raw_image.is_mirrored() && raw_image.has_pixel_data()
+    }
+}
@@ -0,0 +1,71 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Colour used by the games to mark transparent pixels (magenta in 555 format).
+pub const DEFAULT_TRANSPARENT_COLOUR: u16 = 0xf81f;
+
+/// Describes what happens to pixels whose colour is one of the transparent colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransparencyMode {
+    /// The pixel is not written, leaving it as initialised by the builder.
+    #[default]
+    Skip,
+    /// The pixel is written as transparent black.
+    TransparentBlack,
+    /// The pixel is written as an opaque pixel of its colour, like any other pixel.
+    Opaque,
+}
+
+/// Describes how alpha is represented in the loaded pixels.
+///
+/// Pixels without an alpha mask are either fully opaque or transparent black, which are the same in both representations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    /// Colour channels are stored independently of alpha.
+    #[default]
+    Straight,
+    /// Colour channels are multiplied by alpha.
+    Premultiplied,
+}
+
+/// Describes how pixel data of mirrored images is obtained when loading all the images of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MirrorMode {
+    /// Pixel data is decoded again for each mirrored image.
+    #[default]
+    Decode,
+    /// Pixel data is decoded once for the source image and mirrored images are derived from it.
+    Derive,
+}
+
+/// Options controlling how pixel data gets converted when loading images.
+///
+/// The default options match how the games render the images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Colours (in 555 format) treated as transparent.
+    pub transparent_colours: Vec<u16>,
+    /// What to do with pixels of one of the transparent colours.
+    pub transparency_mode: TransparencyMode,
+    /// How alpha from alpha masks is combined with the colour.
+    pub alpha_mode: AlphaMode,
+    /// How mirrored images are loaded, only used when loading all the images of a file.
+    pub mirror_mode: MirrorMode,
+}
+
+impl LoadOptions {
+    pub(crate) fn is_transparent(&self, colour: u16) -> bool {
+        self.transparent_colours.contains(&colour)
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            transparent_colours: vec![DEFAULT_TRANSPARENT_COLOUR],
+            transparency_mode: TransparencyMode::default(),
+            alpha_mode: AlphaMode::default(),
+            mirror_mode: MirrorMode::default(),
+        }
+    }
+}
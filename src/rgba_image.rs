@@ -0,0 +1,87 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use image::{imageops, ImageFormat, RgbaImage};
+
+use crate::sg_file::DataFileReaders;
+use crate::{ImageBuilder, ImageBuilderFactory, LoadOptions, Result, SgFileMetadata, SgImageError};
+
+/// Implementation of [ImageBuilderFactory] for creating images as [RgbaImage] from the `image` crate.
+///
+/// Requires the `image` feature.
+pub struct RgbaImageBuilderFactory;
+
+impl ImageBuilderFactory<RgbaImage> for RgbaImageBuilderFactory {
+    type Builder = RgbaImageBuilder;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        let image = RgbaImage::new(width as u32, height as u32);
+        RgbaImageBuilder { image }
+    }
+}
+
+/// Implementation of [ImageBuilder] for creating images as [RgbaImage] from the `image` crate.
+///
+/// Requires the `image` feature.
+pub struct RgbaImageBuilder {
+    image: RgbaImage,
+}
+
+impl ImageBuilder<RgbaImage> for RgbaImageBuilder {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        let i = position * 4;
+        let pixels: &mut [u8] = &mut self.image;
+        pixels[i..(i + 4)].clone_from_slice(&data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        let i = position * 4 + 3;
+        let pixels: &mut [u8] = &mut self.image;
+        pixels[i] = alpha;
+    }
+
+    fn flip_horizontal(&mut self) {
+        imageops::flip_horizontal_in_place(&mut self.image);
+    }
+
+    fn build(self) -> RgbaImage {
+        self.image
+    }
+}
+
+impl SgFileMetadata {
+    /// Load the image with the given index and save it as a PNG file.
+    ///
+    /// Requires the `image` feature.
+    pub fn save_image_png<P: AsRef<Path>>(&self, index: usize, path: P) -> Result<()> {
+        let image = self.images.get(index).ok_or(SgImageError::InvalidImageIndex(index))?;
+        let mut reader = BufReader::new(File::open(self.get_555_file_path(image.bitmap_id as usize, image.is_external()))?);
+
+        let rgba_image = image.load_image(&mut reader, &RgbaImageBuilderFactory)?;
+        rgba_image.save_with_format(path, ImageFormat::Png)?;
+
+        Ok(())
+    }
+
+    /// Load all images of the given bitmap and save them as PNG files named after the image index in the given folder.
+    ///
+    /// Images with no pixels are skipped.
+    ///
+    /// Requires the `image` feature.
+    pub fn save_bitmap_png<P: AsRef<Path>>(&self, bitmap_id: usize, folder: P) -> Result<()> {
+        let mut readers = DataFileReaders::default();
+
+        for (index, image) in self.images.iter().enumerate() {
+            if image.bitmap_id as usize != bitmap_id || image.width == 0 || image.height == 0 {
+                continue;
+            }
+
+            let path = self.get_555_file_path(bitmap_id, image.is_external());
+            let rgba_image = readers.load_image(image, self.bitmaps.get(bitmap_id), &path, &RgbaImageBuilderFactory, &LoadOptions::default())?;
+            rgba_image.save_with_format(folder.as_ref().join(format!("{index}.png")), ImageFormat::Png)?;
+        }
+
+        Ok(())
+    }
+}
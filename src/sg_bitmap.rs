@@ -1,7 +1,7 @@
 use crate::utils::ReadHelper;
-use crate::Result;
-use std::io::{BufReader, Read, Seek};
-use std::string::String;
+use crate::{Result, TextEncoding};
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Metadata of a bitmap.
 ///
@@ -11,7 +11,9 @@ use std::string::String;
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct SgBitmapMetadata {
     pub id: u32,
+    /// Name of the file with pixel data of external images, decoded from [SgBitmapMetadata::external_filename_bytes].
     pub external_filename: String,
+    /// Decoded from [SgBitmapMetadata::comment_bytes].
     pub comment: String,
     pub width: u32,
     pub height: u32,
@@ -29,12 +31,16 @@ pub struct SgBitmapMetadata {
     pub total_file_size: u32,    // if non-zero -> internal image
     pub file_size_external: u32, // if non-zero -> internal image
     pub unknown_e: [u8; 24],     // 24 unknown bytes
+    /// Raw bytes of the name field, written back in place of [SgBitmapMetadata::external_filename] unless empty.
+    pub external_filename_bytes: Vec<u8>,
+    /// Raw bytes of the comment field, written back in place of [SgBitmapMetadata::comment] unless empty.
+    pub comment_bytes: Vec<u8>,
 }
 
 impl SgBitmapMetadata {
-    pub(crate) fn load<R: Read + Seek>(reader: &mut BufReader<R>, id: u32) -> Result<SgBitmapMetadata> {
-        let external_filename = reader.read_utf(65)?;
-        let comment = reader.read_utf(51)?;
+    pub(crate) fn load<R: ReadHelper>(reader: &mut R, id: u32) -> Result<SgBitmapMetadata> {
+        let external_filename_bytes = reader.read_vec(65)?;
+        let comment_bytes = reader.read_vec(51)?;
         let width = reader.read_u32_le()?;
         let height = reader.read_u32_le()?;
         let num_images = reader.read_u32_le()?;
@@ -54,8 +60,8 @@ impl SgBitmapMetadata {
 
         let sg_bitmap_metadata = SgBitmapMetadata {
             id,
-            external_filename,
-            comment,
+            external_filename: TextEncoding::default().decode(&external_filename_bytes),
+            comment: TextEncoding::default().decode(&comment_bytes),
             width,
             height,
             num_images,
@@ -72,6 +78,8 @@ impl SgBitmapMetadata {
             total_file_size,
             file_size_external,
             unknown_e,
+            external_filename_bytes,
+            comment_bytes,
         };
 
         Ok(sg_bitmap_metadata)
@@ -1,11 +1,32 @@
-use crate::ReadHelper;
+#[cfg(feature = "std")]
+use crate::image_builder::{RecordedImage, RecordingImageBuilderFactory};
+use crate::image_groups::GROUP_COUNT;
 use crate::Result;
 use crate::*;
-use std::fmt::Debug;
+#[cfg(feature = "std")]
+use alloc::collections::btree_map::Entry;
+#[cfg(feature = "std")]
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+#[cfg(feature = "std")]
+use std::io::{BufReader, Read, Seek};
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 
+/// Pixel data of an image, unless it is a mirrored copy of another image.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SgImageData<T> {
+    /// Pixel data decoded for the image.
+    Decoded(T),
+    /// The image is the image with the given index flipped horizontally, no pixel data was decoded for it.
+    Mirrored(usize),
+}
+
 /// Metadata of a sg file.
 ///
 /// Contains metadata of the images retrieved from the sg file.
@@ -24,15 +45,28 @@ pub struct SgFileMetadata {
     pub total_file_size: u32,
     pub file_size_555: u32,
     pub file_size_external: u32,
+    pub unknown_header: [u32; 10],
+    /// Index of the first image of each image group, see [SgFileMetadata::group_image_index].
+    pub group_image_ids: Vec<u16>,
     pub bitmaps: Vec<SgBitmapMetadata>,
+    /// Image records with mirrored images resolved to the record of their source image, used for loading pixel data.
     pub images: Vec<SgImageMetadata>,
+    /// Image records as stored in the file.
+    pub raw_images: Vec<SgImageMetadata>,
 }
 
 impl SgFileMetadata {
-    /// Load metadata from provided reader
-    pub fn load_metadata_from_reader<R: Read + Seek>(reader: &mut BufReader<R>, folder: String, filename: String) -> Result<Self> {
+    /// Load metadata from the contents of a sg file.
+    pub fn load_metadata_from_bytes(data: &[u8], folder: String, filename: String) -> Result<Self> {
+        Self::parse_metadata(data, data.len() as u64, folder, filename)
+    }
+
+    fn parse_metadata(mut data: &[u8], actual_file_size: u64, folder: String, filename: String) -> Result<Self> {
+        let reader = &mut data;
+
         let file_size = reader.read_u32_le()?;
         let version = reader.read_u32_le()?;
+        let format_version = FormatVersion::from_version(version).ok_or(SgImageError::UnsupportedVersion(version))?;
 
         let unknown = reader.read_u32_le()?;
         let max_image_count = reader.read_u32_le()?;
@@ -43,19 +77,27 @@ impl SgFileMetadata {
         let file_size_555 = reader.read_u32_le()?;
         let file_size_external = reader.read_u32_le()?;
 
-        let max_bitmaps_records: u32 = if version == 0xd3 { 100 } else { 200 };
+        let max_bitmaps_records = format_version.max_bitmap_records();
 
-        reader.seek_relative(640)?;
+        if bitmap_count > max_bitmaps_records {
+            return Err(SgImageError::InvalidHeader);
+        }
 
-        let bitmaps = Self::load_bitmaps_metadata(reader, bitmap_count)?;
+        let mut unknown_header = [0; 10];
+        for value in unknown_header.iter_mut() {
+            *value = reader.read_u32_le()?;
+        }
 
-        reader.seek_relative(200 * (max_bitmaps_records - bitmap_count) as i64)?;
+        let group_image_ids = Self::load_group_image_ids(reader)?;
 
-        let images = Self::load_images_metadata(reader, image_count, version >= 0xd6)?;
+        let bitmaps = Self::load_bitmaps_metadata(reader, bitmap_count)?;
+
+        skip_bytes(reader, 200 * (max_bitmaps_records - bitmap_count) as usize)?;
 
-        reader.seek(SeekFrom::End(0))?;
+        let raw_images = Self::load_images_metadata(reader, image_count, format_version.has_alpha())?;
+        let images = Self::resolve_images(&raw_images);
 
-        Self::validate_header(&version, &file_size, &reader.stream_position()?)?;
+        Self::validate_header(&version, &file_size, &actual_file_size)?;
 
         let sg_file = SgFileMetadata {
             folder,
@@ -68,31 +110,34 @@ impl SgFileMetadata {
             total_file_size,
             file_size_555,
             file_size_external,
+            unknown_header,
+            group_image_ids,
             bitmaps,
             images,
+            raw_images,
         };
 
         Ok(sg_file)
     }
 
-    /// Load metadata from the file founds on the given path.
-    pub fn load_metadata_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path.as_ref())?;
-        let mut reader = BufReader::new(file);
-
-        let folder = String::from(path.as_ref().parent().unwrap().to_str().unwrap());
-        let filename = String::from(path.as_ref().file_name().unwrap().to_str().unwrap());
-
-        Self::load_metadata_from_reader(&mut reader, folder, filename)
+    /// Number of bitmap records in a sg file of the given version, including unused ones.
+    pub(crate) fn max_bitmap_records(version: u32) -> u32 {
+        FormatVersion::from_version(version).map_or(200, |format_version| format_version.max_bitmap_records())
     }
 
-    /// Load metadata and pixel data.
-    pub fn load_fully<P: AsRef<Path>, T, F: ImageBuilderFactory<T>>(path: P, image_builder_factory: &F) -> Result<(Self, Vec<T>)> {
-        let sg_file = Self::load_metadata_from_path(path)?;
+    /// Get name of the file containing pixel data for the given bitmap.
+    ///
+    /// The name of a missing bitmap is treated as empty.
+    pub fn get_555_file_name(&self, bitmap_id: usize, is_external: bool) -> String {
+        let basename = if is_external {
+            self.bitmaps.get(bitmap_id).map_or("", |bitmap| bitmap.external_filename.as_str())
+        } else {
+            self.filename.as_str()
+        };
 
-        let images = sg_file.load_image_data(image_builder_factory)?;
+        let stem = basename.rsplit_once('.').map_or(basename, |(stem, _)| stem);
 
-        Ok((sg_file, images))
+        format!("{}.555", stem)
     }
 
     fn validate_header(version: &u32, file_size: &u32, actual_file_size: &u64) -> Result<()> {
@@ -109,7 +154,15 @@ impl SgFileMetadata {
         Ok(())
     }
 
-    fn load_bitmaps_metadata<R: Read + Seek>(reader: &mut BufReader<R>, bitmap_records: u32) -> Result<Vec<SgBitmapMetadata>> {
+    fn load_group_image_ids(reader: &mut &[u8]) -> Result<Vec<u16>> {
+        let mut group_image_ids = Vec::with_capacity(GROUP_COUNT);
+        for _ in 0..GROUP_COUNT {
+            group_image_ids.push(reader.read_u16_le()?);
+        }
+        Ok(group_image_ids)
+    }
+
+    fn load_bitmaps_metadata(reader: &mut &[u8], bitmap_records: u32) -> Result<Vec<SgBitmapMetadata>> {
         let mut bitmaps = Vec::with_capacity(bitmap_records as usize);
         for i in 0..bitmap_records {
             bitmaps.push(SgBitmapMetadata::load(reader, i)?);
@@ -117,63 +170,211 @@ impl SgFileMetadata {
         Ok(bitmaps)
     }
 
-    fn load_images_metadata<R: Read + Seek>(file: &mut BufReader<R>, image_records: u32, alpha: bool) -> Result<Vec<SgImageMetadata>> {
-        let mut images: Vec<SgImageMetadata> = Vec::with_capacity(image_records as usize);
+    fn load_images_metadata(reader: &mut &[u8], image_records: u32, alpha: bool) -> Result<Vec<SgImageMetadata>> {
+        let mut images: Vec<SgImageMetadata> = Vec::with_capacity(image_records as usize + 1);
 
         for i in 0..(image_records + 1) {
-            let mut image = SgImageMetadata::load(file, i, alpha)?;
+            images.push(SgImageMetadata::load(reader, i, alpha)?);
+        }
 
-            let invert_offset = image.invert_offset;
-            if invert_offset != 0 {
-                image = images[(i as i32 + invert_offset) as usize].clone();
-                image.id = i;
-                image.invert_offset = invert_offset;
-            }
+        Ok(images)
+    }
+
+    /// Replace records of mirrored images with the record of their source, keeping their own id and invert offset.
+    pub(crate) fn resolve_images(raw_images: &[SgImageMetadata]) -> Vec<SgImageMetadata> {
+        let mut images: Vec<SgImageMetadata> = Vec::with_capacity(raw_images.len());
+
+        for raw_image in raw_images {
+            // Records mirroring images that are not loaded yet are kept as they are, see [SgFileMetadata::lint]
+            let image = match raw_image.mirror_source().and_then(|source| images.get(source)) {
+                Some(source) => SgImageMetadata { id: raw_image.id, invert_offset: raw_image.invert_offset, ..source.clone() },
+                None => raw_image.clone(),
+            };
 
             images.push(image);
         }
 
-        Ok(images)
+        images
     }
+}
 
-    fn load_image_data<T, F: ImageBuilderFactory<T>>(&self, image_factory_builder: &F) -> Result<Vec<T>> {
-        if self.images.is_empty() {
-            return Ok(Vec::new());
-        }
+#[cfg(feature = "std")]
+impl SgFileMetadata {
+    /// Load metadata from provided reader
+    pub fn load_metadata_from_reader<R: Read + Seek>(reader: &mut BufReader<R>, folder: String, filename: String) -> Result<Self> {
+        let start_position = reader.stream_position()?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
 
-        let mut result = Vec::new();
-        let mut last_file_params = (0, false);
-        let path = self.get_555_file_path(0, false);
-        let mut reader = BufReader::new(File::open(path)?);
+        Self::parse_metadata(&data, start_position + data.len() as u64, folder, filename)
+    }
+
+    /// Load metadata from the file founds on the given path.
+    pub fn load_metadata_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        let mut reader = BufReader::new(file);
+
+        let folder = String::from(path.as_ref().parent().unwrap().to_str().unwrap());
+        let filename = String::from(path.as_ref().file_name().unwrap().to_str().unwrap());
 
-        for i in 0..self.images.len() {
+        Self::load_metadata_from_reader(&mut reader, folder, filename)
+    }
+
+    /// Load metadata and pixel data.
+    pub fn load_fully<P: AsRef<Path>, T, F: TryImageBuilderFactory<T>>(path: P, image_builder_factory: &F) -> Result<(Self, Vec<T>)> {
+        Self::load_fully_with_options(path, image_builder_factory, &LoadOptions::default())
+    }
+
+    /// Load metadata and pixel data, converting pixels as described by the given options.
+    pub fn load_fully_with_options<P: AsRef<Path>, T, F: TryImageBuilderFactory<T>>(
+        path: P,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<(Self, Vec<T>)> {
+        let sg_file = Self::load_metadata_from_path(path)?;
+
+        let images = sg_file.load_image_data(image_builder_factory, options)?;
+
+        Ok((sg_file, images))
+    }
+
+    /// Load metadata and pixel data, without decoding pixel data of mirrored images.
+    ///
+    /// Pixel data of a mirrored image is the pixel data of its source image flipped horizontally, see [SgImageData::Mirrored].
+    pub fn load_fully_deduplicated<P: AsRef<Path>, T, F: TryImageBuilderFactory<T>>(
+        path: P,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<(Self, Vec<SgImageData<T>>)> {
+        let sg_file = Self::load_metadata_from_path(path)?;
+
+        let images = sg_file.load_deduplicated_image_data(image_builder_factory, options)?;
+
+        Ok((sg_file, images))
+    }
+
+    fn load_image_data<T, F: TryImageBuilderFactory<T>>(&self, image_factory_builder: &F, options: &LoadOptions) -> Result<Vec<T>> {
+        let load_order = self.load_order();
+
+        let last_mirrors = match options.mirror_mode {
+            MirrorMode::Decode => BTreeMap::new(),
+            MirrorMode::Derive => self.last_mirrors(&load_order),
+        };
+
+        let mut result: Vec<Option<T>> = (0..self.images.len()).map(|_| None).collect();
+        let mut readers = DataFileReaders::default();
+        let mut recorded_sources: BTreeMap<usize, RecordedImage> = BTreeMap::new();
+
+        for (i, path) in &load_order {
+            let i = *i;
             let image = &self.images[i];
-            let file_params = (image.bitmap_id as usize, image.is_external());
+            let bitmap = self.bitmaps.get(image.bitmap_id as usize);
+
+            if let Some(recorded_source) = self.valid_mirror_source(i).and_then(|source| recorded_sources.get(&source)) {
+                result[i] = Some(recorded_source.replay(image_factory_builder, image, bitmap, true)?);
+            } else if last_mirrors.contains_key(&i) {
+                let recorded = readers.load_image(image, bitmap, path, &RecordingImageBuilderFactory, options)?;
+                result[i] = Some(recorded.replay(image_factory_builder, image, bitmap, false)?);
+                recorded_sources.insert(i, recorded);
+            } else {
+                result[i] = Some(readers.load_image(image, bitmap, path, image_factory_builder, options)?);
+            }
+
+            // Recorded pixels are no longer needed once the last image mirroring them got loaded
+            if let Some(source) = self.valid_mirror_source(i) {
+                if last_mirrors.get(&source) == Some(&i) {
+                    recorded_sources.remove(&source);
+                }
+            }
+        }
 
-            if last_file_params != file_params {
-                let path = self.get_555_file_path(file_params.0, file_params.1);
-                reader = BufReader::new(File::open(path.clone())?);
-                last_file_params = file_params;
+        Ok(result.into_iter().flatten().collect())
+    }
+
+    /// Get indexes of the images along with paths to their data files, in the order of their pixel data in those files.
+    fn load_order(&self) -> Vec<(usize, PathBuf)> {
+        let mut load_order: Vec<(usize, PathBuf)> =
+            self.images.iter().enumerate().map(|(i, image)| (i, self.get_555_file_path(image.bitmap_id as usize, image.is_external()))).collect();
+
+        load_order.sort_by(|(a, a_path), (b, b_path)| a_path.cmp(b_path).then(self.images[*a].data_position().cmp(&self.images[*b].data_position())));
+
+        load_order
+    }
+
+    /// Map indexes of images that have mirrored copies to the index of their last mirrored copy in the load order.
+    fn last_mirrors(&self, load_order: &[(usize, PathBuf)]) -> BTreeMap<usize, usize> {
+        let mut last_mirrors = BTreeMap::new();
+        for (i, _) in load_order {
+            if let Some(source) = self.valid_mirror_source(*i) {
+                last_mirrors.insert(source, *i);
             }
+        }
+        last_mirrors
+    }
+
+    /// Get index of the image the given image is a mirrored copy of, if it is mirrored and its source comes before it.
+    ///
+    /// Images with other mirror sources are decoded from their own record, see [SgFileMetadata::lint].
+    fn valid_mirror_source(&self, i: usize) -> Option<usize> {
+        self.images[i].mirror_source().filter(|source| *source > 0 && *source < i)
+    }
+
+    fn load_deduplicated_image_data<T, F: TryImageBuilderFactory<T>>(&self, image_factory_builder: &F, options: &LoadOptions) -> Result<Vec<SgImageData<T>>> {
+        let mut result: Vec<Option<SgImageData<T>>> = (0..self.images.len()).map(|_| None).collect();
+        let mut readers = DataFileReaders::default();
+
+        for (i, path) in self.load_order() {
+            let image = &self.images[i];
+            let bitmap = self.bitmaps.get(image.bitmap_id as usize);
 
-            result.push(image.load_image(&mut reader, image_factory_builder)?);
+            result[i] = match self.valid_mirror_source(i) {
+                Some(source) => Some(SgImageData::Mirrored(source)),
+                None => Some(SgImageData::Decoded(readers.load_image(image, bitmap, &path, image_factory_builder, options)?)),
+            };
         }
 
-        Ok(result)
+        Ok(result.into_iter().flatten().collect())
     }
 
     /// Get path to the file containing pixel data for the given bitmap.
     pub fn get_555_file_path(&self, bitmap_id: usize, is_external: bool) -> PathBuf {
-        let basename = if is_external {
-            &self.bitmaps[bitmap_id].external_filename
-        } else {
-            &self.filename
-        };
-
-        let filename = format!("{}.555", &basename[..basename.len() - 4]);
+        let filename = self.get_555_file_name(bitmap_id, is_external);
 
         let path_buf: PathBuf = [&self.folder, &filename].iter().collect();
 
         path_buf
     }
 }
+
+/// Readers over the files containing pixel data for the images, kept open while loading images of a file.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub(crate) struct DataFileReaders {
+    readers: BTreeMap<PathBuf, BufReader<File>>,
+}
+
+#[cfg(feature = "std")]
+impl DataFileReaders {
+    /// Load the image from the file at the given path, opening the file unless it is open already.
+    pub(crate) fn load_image<T, F: TryImageBuilderFactory<T>>(
+        &mut self,
+        image: &SgImageMetadata,
+        bitmap: Option<&SgBitmapMetadata>,
+        path: &Path,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        // Files are only opened for images with pixel data in them
+        if !image.has_pixel_data() {
+            return image.decode_image(&mut &[][..], bitmap, image_builder_factory, options);
+        }
+
+        let reader = match self.readers.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(BufReader::new(File::open(path)?)),
+        };
+
+        image.load_image_with_bitmap(reader, bitmap, image_builder_factory, options)
+    }
+}
@@ -1,7 +1,9 @@
-use crate::image_builder::{ImageBuilder, ImageBuilderFactory, ImageBuilderHelper};
+use crate::image_builder::{FallibleBuilder, ImageBuilder, ImageBuilderHelper, PremultipliedAlphaBuilder, TryImageBuilderFactory};
 use crate::Result;
-use crate::{ReadHelper, SgImageError};
+use crate::{skip_bytes, AlphaMode, LoadOptions, ReadHelper, SgBitmapMetadata, SgImageError};
+#[cfg(feature = "std")]
 use std::io::BufReader;
+#[cfg(feature = "std")]
 use std::io::{Read, Seek};
 
 const ISOMETRIC_TILE_WIDTH: u16 = 58;
@@ -45,7 +47,7 @@ pub struct SgImageMetadata {
 }
 
 impl SgImageMetadata {
-    pub(crate) fn load<R: Read + Seek>(reader: &mut BufReader<R>, id: u32, include_alpha: bool) -> Result<SgImageMetadata> {
+    pub(crate) fn load<R: ReadHelper>(reader: &mut R, id: u32, include_alpha: bool) -> Result<SgImageMetadata> {
         let offset = reader.read_u32_le()?;
         let length = reader.read_u32_le()?;
         let uncompressed_length = reader.read_u32_le()?;
@@ -113,41 +115,120 @@ impl SgImageMetadata {
         self.flags[0] > 0
     }
 
-    /// Load pixel data for this image from the provided reader.
-    pub fn load_image<T, F: ImageBuilderFactory<T>, R: Read + Seek>(&self, reader: &mut BufReader<R>, image_builder_factory: &F) -> Result<T> {
-        let mut image_builder = image_builder_factory.new_builder(self.width, self.height);
+    /// Checks if the image is a horizontally mirrored copy of another image.
+    pub fn is_mirrored(&self) -> bool {
+        self.invert_offset != 0
+    }
+
+    /// Get index of the image this image is a mirrored copy of, if it is mirrored.
+    pub fn mirror_source(&self) -> Option<usize> {
+        if self.is_mirrored() {
+            Some((self.id as i64 + self.invert_offset as i64) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Load pixel data for this image from the contents of the file containing its pixel data.
+    pub fn load_image_from_bytes<T, F: TryImageBuilderFactory<T>>(&self, data: &[u8], image_builder_factory: &F, options: &LoadOptions) -> Result<T> {
+        self.load_image_from_bytes_with_bitmap(data, None, image_builder_factory, options)
+    }
 
-        if self.width == 0 || self.height == 0 || self.length == 0 {
-            return Ok(image_builder.build());
+    /// Load pixel data for this image from the contents of the file containing its pixel data,
+    /// passing the bitmap the image belongs to on to [TryImageBuilderFactory::try_new_builder_for_image].
+    pub fn load_image_from_bytes_with_bitmap<T, F: TryImageBuilderFactory<T>>(
+        &self,
+        data: &[u8],
+        bitmap: Option<&SgBitmapMetadata>,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        let mut reader = data;
+
+        if self.has_pixel_data() {
+            skip_bytes(&mut reader, self.data_position() as usize)?;
         }
 
+        self.decode_image(&mut reader, bitmap, image_builder_factory, options)
+    }
+
+    /// Checks if there is any pixel data to be loaded for the image.
+    pub(crate) fn has_pixel_data(&self) -> bool {
+        self.width != 0 && self.height != 0 && self.length != 0
+    }
+
+    /// Get size of the isometric footprint in tiles (along each side), if it is an isometric image.
+    pub fn isometric_size(&self) -> Option<u16> {
         match self.image_type {
-            0 | 1 | 10 | 12 | 13 => self.load_plain_image(&mut image_builder, reader)?,
-            30 => self.load_isometric_image(&mut image_builder, reader)?,
-            256 | 257 | 276 => self.load_sprite_image(&mut image_builder, reader)?,
-            _ => return Err(SgImageError::UnknownImageType(self.image_type)),
+            30 => Some(self.calculate_isometric_size((self.width + 2) / 2)),
+            _ => None,
         }
+    }
 
-        if self.alpha_length > 0 {
-            self.load_alpha_mask(&mut image_builder, reader)?;
+    /// Get position of the pixel data in the file containing it.
+    pub fn data_position(&self) -> u64 {
+        match self.image_type {
+            0 | 1 | 10 | 12 | 13 => (self.offset as u64).saturating_sub(self.flags[0] as u64),
+            _ => self.offset as u64,
         }
+    }
 
-        if self.invert_offset != 0 {
-            image_builder.flip_horizontal();
+    /// Get number of pixels of the image.
+    fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Get length of the pixel data in bytes, including the alpha mask.
+    pub fn data_length(&self) -> usize {
+        self.length as usize + self.alpha_length as usize
+    }
+
+    /// Decode the image from a reader positioned at the start of its pixel data.
+    pub(crate) fn decode_image<T, F: TryImageBuilderFactory<T>, R: ReadHelper>(
+        &self,
+        reader: &mut R,
+        bitmap: Option<&SgBitmapMetadata>,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        let image_builder = image_builder_factory.try_new_builder_for_image(self, bitmap).map_err(SgImageError::builder_error)?;
+        let mut image_builder = FallibleBuilder(image_builder);
+
+        if !self.has_pixel_data() {
+            return image_builder.build().map_err(SgImageError::builder_error);
         }
 
-        Ok(image_builder.build())
+        if self.alpha_length > 0 && options.alpha_mode == AlphaMode::Premultiplied {
+            let mut image_builder = PremultipliedAlphaBuilder::new(image_builder, self.width, self.height);
+            self.load_pixels(&mut image_builder, reader, options)?;
+            return image_builder.build().map_err(SgImageError::builder_error);
+        }
+
+        self.load_pixels(&mut image_builder, reader, options)?;
+
+        image_builder.build().map_err(SgImageError::builder_error)
     }
 
-    fn load_plain_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
-        let current_position = reader.stream_position()?;
+    fn load_pixels<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R, options: &LoadOptions) -> Result<()> {
+        match self.image_type {
+            0 | 1 | 10 | 12 | 13 => self.load_plain_image(image_builder, reader, options)?,
+            30 => self.load_isometric_image(image_builder, reader, options)?,
+            256 | 257 | 276 => self.load_sprite_image(image_builder, reader, options)?,
+            _ => return Err(SgImageError::UnknownImageType(self.image_type)),
+        }
 
-        let relative_position = self.offset as i64 - self.flags[0] as i64 - current_position as i64;
+        if self.alpha_length > 0 {
+            self.load_alpha_mask(image_builder, reader)?;
+        }
 
-        if relative_position != 0 {
-            reader.seek_relative(relative_position)?;
+        if self.is_mirrored() {
+            image_builder.flip_horizontal();
         }
 
+        Ok(())
+    }
+
+    fn load_plain_image<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R, options: &LoadOptions) -> Result<()> {
         // Check image data
         if self.height as u32 * self.width as u32 * 2 != self.length {
             return Err(SgImageError::ImageDataLengthMismatch);
@@ -155,40 +236,50 @@ impl SgImageMetadata {
 
         for position in 0..(self.length as usize) / 2 {
             let colour = reader.read_u16_le()?;
-            image_builder.set_555_pixel_by_pos(position, colour);
+            image_builder.set_555_pixel_by_pos(position, colour, options);
         }
 
         Ok(())
     }
 
-    fn load_isometric_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
-        let current_position = reader.stream_position()?;
+    fn load_isometric_image<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R, options: &LoadOptions) -> Result<()> {
+        self.load_isometric_base(image_builder, reader, options)?;
+        let length = self.length.checked_sub(self.uncompressed_length).ok_or(SgImageError::ImageDataLengthMismatch)?;
+        self.load_transparent_image(image_builder, reader, &length, options)?;
+
+        Ok(())
+    }
 
-        let relative_position = self.offset as i64 - current_position as i64;
+    fn load_isometric_base<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R, options: &LoadOptions) -> Result<()> {
+        let height = (self.width + 2) / 2;
 
-        if relative_position != 0 {
-            reader.seek_relative(relative_position)?;
+        if ((self.width as u32 + 2) * height as u32) != self.uncompressed_length {
+            return Err(SgImageError::ImageDataLengthMismatch);
         }
 
-        self.load_isometric_base(image_builder, reader)?;
-        self.load_transparent_image(image_builder, reader, &(self.length - self.uncompressed_length))?;
+        let pixel_count = self.pixel_count();
 
-        Ok(())
+        self.for_each_isometric_base_pixel(|position| {
+            if position >= pixel_count {
+                return Err(SgImageError::ImageDataLengthMismatch);
+            }
+
+            let c = reader.read_u16_le()?;
+            image_builder.set_555_pixel_by_pos(position, c, options);
+            Ok(())
+        })
     }
 
-    fn load_isometric_base<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut R) -> Result<()> {
+    /// Call the given function with positions of all pixels of the isometric base, in the order they are stored in.
+    pub(crate) fn for_each_isometric_base_pixel<F: FnMut(usize) -> Result<()>>(&self, mut f: F) -> Result<()> {
         let width = self.width;
         let height = (width + 2) / 2; // 58 -> 39, 118 -> 60 etc
         let size = self.calculate_isometric_size(height);
         let (_tile_bytes, tile_height, tile_width) = Self::calculate_tile_size(&size, &height);
-        let height_offset = self.height - height;
+        let height_offset = self.height.checked_sub(height).ok_or(SgImageError::ImageDataLengthMismatch)?;
 
         let mut y_offset = height_offset;
 
-        if ((width as u32 + 2) * height as u32) != self.uncompressed_length {
-            return Err(SgImageError::ImageDataLengthMismatch);
-        }
-
         for y in 0..(size + size - 1) {
             let (x_lim, mut x_offset) = if y < size {
                 (y + 1, (size - y - 1) * tile_height)
@@ -197,7 +288,8 @@ impl SgImageMetadata {
             };
 
             for _x in 0..x_lim {
-                self.write_isometric_tile(image_builder, reader, x_offset as usize, y_offset as usize, tile_width as usize, tile_height as usize)?;
+                let position = x_offset as usize + (y_offset as usize * self.width as usize);
+                self.visit_isometric_tile(position, tile_width as usize, tile_height as usize, &mut f)?;
                 x_offset += tile_width + 2;
             }
 
@@ -207,53 +299,35 @@ impl SgImageMetadata {
         Ok(())
     }
 
-    fn load_sprite_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>) -> Result<()> {
-        let current_position = reader.stream_position()?;
-
-        let relative_position = self.offset as i64 - current_position as i64;
-
-        if relative_position != 0 {
-            reader.seek_relative(relative_position)?;
-        }
-
-        self.load_transparent_image(image_builder, reader, &self.length)?;
+    fn load_sprite_image<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R, options: &LoadOptions) -> Result<()> {
+        self.load_transparent_image(image_builder, reader, &self.length, options)?;
         Ok(())
     }
 
     fn calculate_isometric_size(&self, height: u16) -> u16 {
         if self.flags[3] == 0 {
-            if (height % ISOMETRIC_TILE_HEIGHT) == 0 {
+            if height.is_multiple_of(ISOMETRIC_TILE_HEIGHT) {
                 return height / ISOMETRIC_TILE_HEIGHT;
-            } else if (height % ISOMETRIC_LARGE_TILE_HEIGHT) == 0 {
+            } else if height.is_multiple_of(ISOMETRIC_LARGE_TILE_HEIGHT) {
                 return height / ISOMETRIC_LARGE_TILE_HEIGHT;
             }
         }
         self.flags[3] as u16
     }
 
-    fn write_isometric_tile<T, B: ImageBuilder<T>, R: Read + Seek>(
-        &self,
-        image_builder: &mut B,
-        reader: &mut R,
-        offset_x: usize,
-        offset_y: usize,
-        tile_width: usize,
-        tile_height: usize,
-    ) -> Result<()> {
+    fn visit_isometric_tile<F: FnMut(usize) -> Result<()>>(&self, mut position: usize, tile_width: usize, tile_height: usize, f: &mut F) -> Result<()> {
         let half_height = tile_height / 2;
 
         let mut x_start = tile_height;
         let mut x_end = tile_width - x_start;
-        let mut position = offset_x + (offset_y * self.width as usize);
-        let skip = (self.width as usize) - tile_width;
+        let skip = (self.width as usize).checked_sub(tile_width).ok_or(SgImageError::ImageDataLengthMismatch)?;
 
         for _y in 0..half_height {
             x_start -= 2;
             x_end += 2;
             position += x_start;
             for _x in x_start..x_end {
-                let c = reader.read_u16_le()?;
-                image_builder.set_555_pixel_by_pos(position, c);
+                f(position)?;
                 position += 1;
             }
             position += x_start + skip;
@@ -262,8 +336,7 @@ impl SgImageMetadata {
         for _y in half_height..tile_height {
             position += x_start;
             for _x in x_start..x_end {
-                let c = reader.read_u16_le()?;
-                image_builder.set_555_pixel_by_pos(position, c);
+                f(position)?;
                 position += 1;
             }
             position += x_start + skip;
@@ -282,9 +355,16 @@ impl SgImageMetadata {
         }
     }
 
-    fn load_transparent_image<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut BufReader<R>, length: &u32) -> Result<()> {
+    fn load_transparent_image<T, B: ImageBuilder<T>, R: ReadHelper>(
+        &self,
+        image_builder: &mut B,
+        reader: &mut R,
+        length: &u32,
+        options: &LoadOptions,
+    ) -> Result<()> {
         let mut pos = 0;
         let mut remaining_bytes = *length as usize;
+        let pixel_count = self.pixel_count();
 
         while remaining_bytes > 0 {
             let c = reader.read_u8()? as usize;
@@ -292,13 +372,16 @@ impl SgImageMetadata {
             if c == 255 {
                 // The next number is pixels to skip
                 pos += reader.read_u8()? as usize;
-                remaining_bytes -= 2;
+                remaining_bytes = remaining_bytes.checked_sub(2).ok_or(SgImageError::ImageDataLengthMismatch)?;
             } else {
-                remaining_bytes -= 1 + (c * 2);
+                remaining_bytes = remaining_bytes.checked_sub(1 + (c * 2)).ok_or(SgImageError::ImageDataLengthMismatch)?;
+                if pos + c > pixel_count {
+                    return Err(SgImageError::ImageDataLengthMismatch);
+                }
                 // Pixels to fill in
                 for _j in 0..c {
                     let pixel = reader.read_u16_le()?;
-                    image_builder.set_555_pixel_by_pos(pos, pixel);
+                    image_builder.set_555_pixel_by_pos(pos, pixel, options);
                     pos += 1;
                 }
             }
@@ -307,9 +390,10 @@ impl SgImageMetadata {
         Ok(())
     }
 
-    fn load_alpha_mask<T, B: ImageBuilder<T>, R: Read + Seek>(&self, image_builder: &mut B, reader: &mut R) -> Result<()> {
+    fn load_alpha_mask<T, B: ImageBuilder<T>, R: ReadHelper>(&self, image_builder: &mut B, reader: &mut R) -> Result<()> {
         let mut pos = 0;
         let mut remaining_bytes = self.alpha_length as usize;
+        let pixel_count = self.pixel_count();
 
         while remaining_bytes > 0 {
             let c = reader.read_u8()? as usize;
@@ -317,10 +401,13 @@ impl SgImageMetadata {
             if c == 255 {
                 // The next number is pixels to skip
                 pos += reader.read_u8()? as usize;
-                remaining_bytes -= 2;
+                remaining_bytes = remaining_bytes.checked_sub(2).ok_or(SgImageError::ImageDataLengthMismatch)?;
             } else {
                 // Pixels to fill in
-                remaining_bytes -= 1 + c;
+                remaining_bytes = remaining_bytes.checked_sub(1 + c).ok_or(SgImageError::ImageDataLengthMismatch)?;
+                if pos + c > pixel_count {
+                    return Err(SgImageError::ImageDataLengthMismatch);
+                }
                 for _j in 0..c {
                     let alpha = reader.read_u8()?;
                     image_builder.set_alpha(pos, alpha << 3);
@@ -332,3 +419,47 @@ impl SgImageMetadata {
         Ok(())
     }
 }
+
+#[cfg(feature = "std")]
+impl SgImageMetadata {
+    /// Load pixel data for this image from the provided reader.
+    pub fn load_image<T, F: TryImageBuilderFactory<T>, R: Read + Seek>(&self, reader: &mut BufReader<R>, image_builder_factory: &F) -> Result<T> {
+        self.load_image_with_options(reader, image_builder_factory, &LoadOptions::default())
+    }
+
+    /// Load pixel data for this image from the provided reader, converting pixels as described by the given options.
+    pub fn load_image_with_options<T, F: TryImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        self.load_image_with_bitmap(reader, None, image_builder_factory, options)
+    }
+
+    /// Load pixel data for this image from the provided reader, converting pixels as described by the given options,
+    /// passing the bitmap the image belongs to on to [TryImageBuilderFactory::try_new_builder_for_image].
+    pub fn load_image_with_bitmap<T, F: TryImageBuilderFactory<T>, R: Read + Seek>(
+        &self,
+        reader: &mut BufReader<R>,
+        bitmap: Option<&SgBitmapMetadata>,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<T> {
+        self.seek_to_data(reader)?;
+        self.decode_image(reader, bitmap, image_builder_factory, options)
+    }
+
+    /// Move the reader to the start of pixel data of this image, if it has any.
+    pub(crate) fn seek_to_data<R: Read + Seek>(&self, reader: &mut BufReader<R>) -> Result<()> {
+        if self.has_pixel_data() {
+            let relative_position = self.data_position() as i64 - reader.stream_position()? as i64;
+
+            if relative_position != 0 {
+                reader.seek_relative(relative_position)?;
+            }
+        }
+
+        Ok(())
+    }
+}
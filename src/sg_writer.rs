@@ -0,0 +1,243 @@
+use crate::image_groups::GROUP_COUNT;
+use crate::{Result, SgBitmapMetadata, SgFileMetadata, SgImageError, SgImageMetadata, WriteHelper, DEFAULT_TRANSPARENT_COLOUR};
+use alloc::vec;
+use alloc::vec::Vec;
+
+impl SgFileMetadata {
+    /// Get contents of the sg file described by this metadata.
+    ///
+    /// Image records are written from [SgFileMetadata::raw_images], unused bitmap records are filled with zeroes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        data.write_u32_le(self.file_size);
+        data.write_u32_le(self.version);
+        data.write_u32_le(self.unknown);
+        data.write_u32_le(self.max_image_count);
+        data.write_u32_le(self.raw_images.len().saturating_sub(1) as u32);
+        data.write_u32_le(self.bitmaps.len() as u32);
+        data.write_u32_le(self.bitmap_records_without_system);
+        data.write_u32_le(self.total_file_size);
+        data.write_u32_le(self.file_size_555);
+        data.write_u32_le(self.file_size_external);
+
+        for value in self.unknown_header {
+            data.write_u32_le(value);
+        }
+
+        for group in 0..GROUP_COUNT {
+            data.write_u16_le(self.group_image_ids.get(group).copied().unwrap_or(0));
+        }
+
+        for bitmap in &self.bitmaps {
+            bitmap.write(&mut data);
+        }
+
+        let unused_bitmap_records = (Self::max_bitmap_records(self.version) as usize).saturating_sub(self.bitmaps.len());
+        data.resize(data.len() + 200 * unused_bitmap_records, 0);
+
+        for image in &self.raw_images {
+            image.write(&mut data, self.version >= 0xd6);
+        }
+
+        data
+    }
+}
+
+impl SgBitmapMetadata {
+    pub(crate) fn write(&self, data: &mut Vec<u8>) {
+        data.write_string(&self.external_filename, &self.external_filename_bytes, 65);
+        data.write_string(&self.comment, &self.comment_bytes, 51);
+        data.write_u32_le(self.width);
+        data.write_u32_le(self.height);
+        data.write_u32_le(self.num_images);
+        data.write_u32_le(self.start_index);
+        data.write_u32_le(self.end_index);
+        data.write_u32_le(self.image_id);
+        data.write_u32_le(self.unknown_a);
+        data.write_u32_le(self.unknown_b);
+        data.write_u32_le(self.unknown_c);
+        data.write_u32_le(self.unknown_d);
+        data.write_u32_le(self.image_width);
+        data.write_u32_le(self.image_height);
+        data.write_u32_le(self.file_size_555);
+        data.write_u32_le(self.total_file_size);
+        data.write_u32_le(self.file_size_external);
+        data.write_from(&self.unknown_e);
+    }
+}
+
+impl SgImageMetadata {
+    pub(crate) fn write(&self, data: &mut Vec<u8>, include_alpha: bool) {
+        data.write_u32_le(self.offset);
+        data.write_u32_le(self.length);
+        data.write_u32_le(self.uncompressed_length);
+        data.write_from(&self.zeroes);
+        data.write_i32_le(self.invert_offset);
+        data.write_u16_le(self.width);
+        data.write_u16_le(self.height);
+        for value in self.unknown_a {
+            data.write_u16_le(value);
+        }
+        data.write_u16_le(self.anim_sprites);
+        data.write_u16_le(self.unknown_b);
+        data.write_u16_le(self.x_offset);
+        data.write_u16_le(self.y_offset);
+        data.write_from(&self.unknown_c);
+        data.write_u8(self.is_reversible);
+        data.write_u8(self.unknown_d);
+        data.write_u16_le(self.image_type);
+        data.write_from(&self.flags);
+        data.write_u8(self.bitmap_id);
+        data.write_u8(self.unknown_e);
+        data.write_u8(self.anim_speed_id);
+        data.write_from(&self.unknown_f);
+        if include_alpha {
+            data.write_u32_le(self.alpha_offset);
+            data.write_u32_le(self.alpha_length);
+        }
+    }
+
+    /// Encode RGBA pixels as pixel data of this image, as stored in .555 files.
+    ///
+    /// The encoding depends on the type, dimensions and isometric size (`flags[3]`) of the image, which have to be set beforehand.
+    /// Fully transparent pixels are stored as transparent.
+    /// Partially transparent pixels are stored in an alpha mask if `include_alpha` is set (as used by 0xd6 files) and as opaque otherwise.
+    ///
+    /// Sets `length`, `uncompressed_length` and `alpha_length` of the image, offsets are left for the caller to set.
+    pub fn encode_image(&mut self, pixels: &[u8], include_alpha: bool) -> Result<Vec<u8>> {
+        let pixel_count = self.width as usize * self.height as usize;
+
+        if pixels.len() != pixel_count * 4 {
+            return Err(SgImageError::ImageDataLengthMismatch);
+        }
+
+        let mut data = Vec::new();
+
+        if pixel_count > 0 {
+            match self.image_type {
+                0 | 1 | 10 | 12 | 13 => {
+                    for pixel in pixels.chunks_exact(4) {
+                        data.write_u16_le(opaque_colour(pixel));
+                    }
+                    self.uncompressed_length = data.len() as u32;
+                }
+                30 => self.encode_isometric_image(pixels, &mut data)?,
+                256 | 257 | 276 => {
+                    encode_runs(pixel_count, |position| visible_colour(pixels, position), |data, colour| data.write_u16_le(colour), &mut data);
+                    self.uncompressed_length = 0;
+                }
+                _ => return Err(SgImageError::UnknownImageType(self.image_type)),
+            }
+        }
+
+        self.length = data.len() as u32;
+        self.alpha_length = 0;
+
+        if include_alpha && pixels.chunks_exact(4).any(|pixel| pixel[3] != 0 && pixel[3] != 255) {
+            encode_runs(pixel_count, |position| partial_alpha(pixels, position), |data, alpha| data.write_u8(alpha), &mut data);
+            self.alpha_length = data.len() as u32 - self.length;
+        }
+
+        Ok(data)
+    }
+
+    fn encode_isometric_image(&mut self, pixels: &[u8], data: &mut Vec<u8>) -> Result<()> {
+        let pixel_count = self.width as usize * self.height as usize;
+
+        if (self.height as usize) < (self.width as usize + 2) / 2 {
+            return Err(SgImageError::ImageDataLengthMismatch);
+        }
+
+        let mut is_base = vec![false; pixel_count];
+
+        self.for_each_isometric_base_pixel(|position| {
+            let pixel = pixels.get(position * 4..position * 4 + 4).ok_or(SgImageError::ImageDataLengthMismatch)?;
+            data.write_u16_le(opaque_colour(pixel));
+            is_base[position] = true;
+            Ok(())
+        })?;
+
+        // The base has to be made of whole tiles
+        if data.len() != (self.width as usize + 2) * ((self.width as usize + 2) / 2) {
+            return Err(SgImageError::ImageDataLengthMismatch);
+        }
+
+        self.uncompressed_length = data.len() as u32;
+
+        encode_runs(
+            pixel_count,
+            |position| if is_base[position] { None } else { visible_colour(pixels, position) },
+            |data, colour| data.write_u16_le(colour),
+            data,
+        );
+
+        Ok(())
+    }
+}
+
+/// Run-length encode the pixels the way transparent images and alpha masks are stored.
+///
+/// Pixels with no value are skipped, pixels at the end of the image need no data when skipped.
+fn encode_runs<T, V: Fn(usize) -> Option<T>, W: Fn(&mut Vec<u8>, T)>(pixel_count: usize, value: V, write: W, data: &mut Vec<u8>) {
+    let mut position = 0;
+
+    while position < pixel_count {
+        let run_start = position;
+
+        if value(position).is_none() {
+            while position < pixel_count && position - run_start < 255 && value(position).is_none() {
+                position += 1;
+            }
+
+            if position < pixel_count {
+                data.write_u8(255);
+                data.write_u8((position - run_start) as u8);
+            }
+        } else {
+            let mut values = Vec::new();
+
+            while position < pixel_count && position - run_start < 254 {
+                match value(position) {
+                    Some(value) => values.push(value),
+                    None => break,
+                }
+                position += 1;
+            }
+
+            data.write_u8(values.len() as u8);
+            for value in values {
+                write(data, value);
+            }
+        }
+    }
+}
+
+fn colour_555(pixel: &[u8]) -> u16 {
+    ((pixel[0] as u16 >> 3) << 10) | ((pixel[1] as u16 >> 3) << 5) | (pixel[2] as u16 >> 3)
+}
+
+/// Colour of the pixel, or the transparent colour if it is fully transparent.
+fn opaque_colour(pixel: &[u8]) -> u16 {
+    if pixel[3] == 0 {
+        DEFAULT_TRANSPARENT_COLOUR
+    } else {
+        colour_555(pixel)
+    }
+}
+
+fn visible_colour(pixels: &[u8], position: usize) -> Option<u16> {
+    let pixel = &pixels[position * 4..position * 4 + 4];
+    if pixel[3] == 0 {
+        None
+    } else {
+        Some(colour_555(pixel))
+    }
+}
+
+fn partial_alpha(pixels: &[u8], position: usize) -> Option<u8> {
+    match pixels[position * 4 + 3] {
+        0 | 255 => None,
+        alpha => Some(alpha >> 3),
+    }
+}
@@ -0,0 +1,265 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::SgFileMetadata;
+
+/// Statistics of the images of a bitmap, or of all the images of a file, see [SgFileMetadata::stats].
+///
+/// Mirrored images count towards the dimensions of the images, but not towards the size of pixel data as they have none of their own.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImageStats {
+    pub image_count: usize,
+    /// Number of images of each image type.
+    pub images_by_type: BTreeMap<u16, usize>,
+    pub mirrored_count: usize,
+    pub total_width: u64,
+    pub total_height: u64,
+    /// Sum of the lengths of pixel data, as stored in the data files.
+    pub compressed_bytes: u64,
+    /// Sum of the uncompressed lengths stated by the images.
+    pub uncompressed_bytes: u64,
+    /// Size of all the images as raw RGBA pixels.
+    pub rgba_bytes: u64,
+    /// Sum of the lengths of alpha masks.
+    pub alpha_bytes: u64,
+    /// Pixel data (including alpha masks) stored in the .555 file of the sg file.
+    pub internal_bytes: u64,
+    /// Pixel data (including alpha masks) stored in external .555 files.
+    pub external_bytes: u64,
+}
+
+/// Statistics of the images of a bitmap.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BitmapStats {
+    pub bitmap_id: usize,
+    pub name: String,
+    pub images: ImageStats,
+}
+
+/// Statistics of a sg file, per bitmap and in total.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SgFileStats {
+    pub filename: String,
+    pub bitmaps: Vec<BitmapStats>,
+    pub total: ImageStats,
+}
+
+impl ImageStats {
+    /// Average width of the images, 0 if there are no images.
+    pub fn average_width(&self) -> f64 {
+        if self.image_count == 0 {
+            0.0
+        } else {
+            self.total_width as f64 / self.image_count as f64
+        }
+    }
+
+    /// Average height of the images, 0 if there are no images.
+    pub fn average_height(&self) -> f64 {
+        if self.image_count == 0 {
+            0.0
+        } else {
+            self.total_height as f64 / self.image_count as f64
+        }
+    }
+
+    /// Size of the pixel data relative to the size of raw RGBA pixels, 0 if there are no pixels.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.rgba_bytes == 0 {
+            0.0
+        } else {
+            (self.internal_bytes + self.external_bytes) as f64 / self.rgba_bytes as f64
+        }
+    }
+
+    fn types_summary(&self, separator: &str) -> String {
+        let types: Vec<String> = self.images_by_type.iter().map(|(image_type, count)| format!("{}:{}", image_type, count)).collect();
+        types.join(separator)
+    }
+}
+
+impl SgFileMetadata {
+    /// Gather statistics of the images of each bitmap and of the whole file, without loading any pixel data.
+    pub fn stats(&self) -> SgFileStats {
+        let mut stats = SgFileStats {
+            filename: self.filename.clone(),
+            bitmaps: self
+                .bitmaps
+                .iter()
+                .enumerate()
+                .map(|(bitmap_id, bitmap)| BitmapStats { bitmap_id, name: bitmap.external_filename.clone(), images: ImageStats::default() })
+                .collect(),
+            total: ImageStats::default(),
+        };
+
+        for (raw_image, image) in self.raw_images.iter().zip(self.images.iter()).skip(1) {
+            let bitmap_stats = stats.bitmaps.get_mut(raw_image.bitmap_id as usize).map(|bitmap_stats| &mut bitmap_stats.images);
+
+            for image_stats in core::iter::once(&mut stats.total).chain(bitmap_stats) {
+                image_stats.image_count += 1;
+                *image_stats.images_by_type.entry(image.image_type).or_default() += 1;
+                image_stats.total_width += image.width as u64;
+                image_stats.total_height += image.height as u64;
+                image_stats.rgba_bytes += image.width as u64 * image.height as u64 * 4;
+
+                if raw_image.is_mirrored() {
+                    image_stats.mirrored_count += 1;
+                    continue;
+                }
+
+                image_stats.compressed_bytes += raw_image.length as u64;
+                image_stats.uncompressed_bytes += raw_image.uncompressed_length as u64;
+                image_stats.alpha_bytes += raw_image.alpha_length as u64;
+
+                if raw_image.is_external() {
+                    image_stats.external_bytes += raw_image.data_length() as u64;
+                } else {
+                    image_stats.internal_bytes += raw_image.data_length() as u64;
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+impl SgFileStats {
+    /// Render the statistics as a text table, with a row per bitmap and a row with the totals.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        let _ = writeln!(text, "{}", self.filename);
+        let _ = writeln!(
+            text,
+            "{:>6} {:<24} {:>7} {:>8} {:>13} {:>12} {:>12} {:>12} {:>10} {:>12} {:>12} {:>6}  types",
+            "bitmap", "name", "images", "mirrored", "avg size", "compressed", "uncompressed", "rgba", "alpha", "internal", "external", "ratio"
+        );
+
+        for bitmap in &self.bitmaps {
+            Self::write_text_row(&mut text, &format!("{}", bitmap.bitmap_id), &bitmap.name, &bitmap.images);
+        }
+
+        Self::write_text_row(&mut text, "total", "", &self.total);
+
+        text
+    }
+
+    fn write_text_row(text: &mut String, bitmap: &str, name: &str, images: &ImageStats) {
+        let _ = writeln!(
+            text,
+            "{:>6} {:<24} {:>7} {:>8} {:>13} {:>12} {:>12} {:>12} {:>10} {:>12} {:>12} {:>6.3}  {}",
+            bitmap,
+            name,
+            images.image_count,
+            images.mirrored_count,
+            format!("{:.1}x{:.1}", images.average_width(), images.average_height()),
+            images.compressed_bytes,
+            images.uncompressed_bytes,
+            images.rgba_bytes,
+            images.alpha_bytes,
+            images.internal_bytes,
+            images.external_bytes,
+            images.compression_ratio(),
+            images.types_summary(" "),
+        );
+    }
+
+    /// Render the statistics as CSV, with a row per bitmap and a row with the totals (with an empty bitmap id).
+    ///
+    /// Image counts by type are given as `type:count` pairs separated by `;`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "bitmap,name,images,mirrored,average_width,average_height,compressed_bytes,uncompressed_bytes,rgba_bytes,alpha_bytes,internal_bytes,external_bytes,types\n",
+        );
+
+        for bitmap in &self.bitmaps {
+            Self::write_csv_row(&mut csv, &format!("{}", bitmap.bitmap_id), &bitmap.name, &bitmap.images);
+        }
+
+        Self::write_csv_row(&mut csv, "", "total", &self.total);
+
+        csv
+    }
+
+    fn write_csv_row(csv: &mut String, bitmap: &str, name: &str, images: &ImageStats) {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{:.2},{:.2},{},{},{},{},{},{},{}",
+            bitmap,
+            csv_field(name),
+            images.image_count,
+            images.mirrored_count,
+            images.average_width(),
+            images.average_height(),
+            images.compressed_bytes,
+            images.uncompressed_bytes,
+            images.rgba_bytes,
+            images.alpha_bytes,
+            images.internal_bytes,
+            images.external_bytes,
+            images.types_summary(";"),
+        );
+    }
+
+    /// Render the statistics as JSON.
+    pub fn to_json(&self) -> String {
+        let bitmaps: Vec<String> = self
+            .bitmaps
+            .iter()
+            .map(|bitmap| format!("{{\"bitmap\":{},\"name\":{},\"images\":{}}}", bitmap.bitmap_id, json_string(&bitmap.name), Self::images_json(&bitmap.images)))
+            .collect();
+
+        format!("{{\"filename\":{},\"bitmaps\":[{}],\"total\":{}}}", json_string(&self.filename), bitmaps.join(","), Self::images_json(&self.total))
+    }
+
+    fn images_json(images: &ImageStats) -> String {
+        let types: Vec<String> = images.images_by_type.iter().map(|(image_type, count)| format!("\"{}\":{}", image_type, count)).collect();
+
+        format!(
+            "{{\"count\":{},\"by_type\":{{{}}},\"mirrored\":{},\"average_width\":{},\"average_height\":{},\"compressed_bytes\":{},\"uncompressed_bytes\":{},\"rgba_bytes\":{},\"alpha_bytes\":{},\"internal_bytes\":{},\"external_bytes\":{}}}",
+            images.image_count,
+            types.join(","),
+            images.mirrored_count,
+            images.average_width(),
+            images.average_height(),
+            images.compressed_bytes,
+            images.uncompressed_bytes,
+            images.rgba_bytes,
+            images.alpha_bytes,
+            images.internal_bytes,
+            images.external_bytes,
+        )
+    }
+}
+
+/// Quote the CSV field if needed.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+/// Quote and escape the value as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
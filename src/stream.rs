@@ -0,0 +1,72 @@
+use std::io::{self, Read};
+
+use crate::{LoadOptions, Result, SgFileMetadata, SgImageError, TryImageBuilderFactory};
+
+impl SgFileMetadata {
+    /// Load pixel data for all the images stored in the given data file, from a reader that can only be read forward.
+    ///
+    /// The data file is identified by its name, as returned by [SgFileMetadata::get_555_file_name].
+    /// Images are decoded in the order of their pixel data in the file, skipping over any data in between them,
+    /// so the reader does not need to implement `Seek` (e.g. data decompressed on the fly).
+    /// Returns indexes of the images along with their pixel data, in index order.
+    pub fn load_images_from_stream<R: Read, T, F: TryImageBuilderFactory<T>>(
+        &self,
+        data_file_name: &str,
+        reader: &mut R,
+        image_builder_factory: &F,
+        options: &LoadOptions,
+    ) -> Result<Vec<(usize, T)>> {
+        let mut indexes: Vec<usize> = (0..self.images.len())
+            .filter(|&i| {
+                let image = &self.images[i];
+                self.get_555_file_name(image.bitmap_id as usize, image.is_external()) == data_file_name
+            })
+            .collect();
+
+        indexes.sort_by_key(|&i| self.images[i].data_position());
+
+        let mut result = Vec::with_capacity(indexes.len());
+        let mut position = 0;
+        let mut span = Vec::new();
+        let mut span_start = 0;
+
+        for i in indexes {
+            let image = &self.images[i];
+            let bitmap = self.bitmaps.get(image.bitmap_id as usize);
+
+            if !image.has_pixel_data() {
+                result.push((i, image.decode_image(&mut &[][..], bitmap, image_builder_factory, options)?));
+                continue;
+            }
+
+            let start = image.data_position();
+            let end = start + image.data_length() as u64;
+
+            // Images sharing their pixel data (e.g. mirrored copies) are decoded from the last span read
+            if start < position {
+                let data = span.get((start - span_start) as usize..(end - span_start) as usize).ok_or(SgImageError::ImageDataLengthMismatch)?;
+                result.push((i, image.decode_image(&mut &data[..], bitmap, image_builder_factory, options)?));
+                continue;
+            }
+
+            let skipped = io::copy(&mut reader.by_ref().take(start - position), &mut io::sink())?;
+            if skipped < start - position {
+                return Err(SgImageError::UnexpectedEof);
+            }
+
+            span.resize(image.data_length(), 0);
+            match reader.read_exact(&mut span) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Err(SgImageError::UnexpectedEof),
+                result => result?,
+            }
+            span_start = start;
+            position = end;
+
+            result.push((i, image.decode_image(&mut &span[..], bitmap, image_builder_factory, options)?));
+        }
+
+        result.sort_by_key(|(i, _)| *i);
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,89 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{SgBitmapMetadata, SgFileMetadata};
+
+/// Characters of Windows-1252 bytes 0x80 to 0x9f, bytes undefined in the code page are mapped to the matching C1 control characters.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}',
+    '\u{17d}', '\u{8f}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}',
+    '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+/// Encoding of the strings stored in the sg files (names and comments of bitmaps).
+///
+/// Strings are decoded up to the first NUL byte, as the bytes after it are often left over garbage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEncoding {
+    /// Windows-1252, used by the western releases of the games.
+    #[default]
+    Windows1252,
+    /// ISO-8859-1, mapping each byte to the character of the same value.
+    Latin1,
+    /// UTF-8, with invalid sequences replaced by the replacement character.
+    Utf8Lossy,
+}
+
+impl TextEncoding {
+    /// Decode the string stored in the given bytes.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let bytes = until_nul(bytes);
+
+        match self {
+            TextEncoding::Windows1252 => bytes.iter().map(|&byte| windows_1252_char(byte)).collect(),
+            TextEncoding::Latin1 => bytes.iter().map(|&byte| char::from(byte)).collect(),
+            TextEncoding::Utf8Lossy => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+impl SgFileMetadata {
+    /// Decode names and comments of all the bitmaps again from their raw bytes, with the given encoding.
+    pub fn decode_strings(&mut self, encoding: TextEncoding) {
+        self.decode_strings_with(|bytes| encoding.decode(bytes));
+    }
+
+    /// Decode names and comments of all the bitmaps again from their raw bytes, with the given function.
+    ///
+    /// Allows using code pages not covered by [TextEncoding], the function is given the bytes up to the first NUL byte.
+    pub fn decode_strings_with<F: Fn(&[u8]) -> String>(&mut self, decode: F) {
+        for bitmap in &mut self.bitmaps {
+            bitmap.decode_strings_with(&decode);
+        }
+    }
+}
+
+impl SgBitmapMetadata {
+    /// Decode the name and comment of the bitmap again from their raw bytes, with the given function.
+    pub fn decode_strings_with<F: Fn(&[u8]) -> String>(&mut self, decode: F) {
+        self.external_filename = decode(until_nul(&self.external_filename_bytes));
+        self.comment = decode(until_nul(&self.comment_bytes));
+    }
+}
+
+/// Checks if any of the built-in encodings decodes the bytes to the given string.
+pub(crate) fn decodes_to(bytes: &[u8], value: &str) -> bool {
+    [TextEncoding::Windows1252, TextEncoding::Latin1, TextEncoding::Utf8Lossy].iter().any(|encoding| encoding.decode(bytes) == value)
+}
+
+/// Encode the string as Windows-1252, with characters missing from it replaced by `?`.
+pub(crate) fn encode_windows_1252(value: &str) -> Vec<u8> {
+    value
+        .chars()
+        .map(|c| match c as u32 {
+            0..=0x7f | 0xa0..=0xff => c as u8,
+            _ => WINDOWS_1252_HIGH.iter().position(|&high| high == c).map_or(b'?', |i| 0x80 + i as u8),
+        })
+        .collect()
+}
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9f => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+        _ => char::from(byte),
+    }
+}
+
+fn until_nul(bytes: &[u8]) -> &[u8] {
+    bytes.iter().position(|&byte| byte == 0).map_or(bytes, |end| &bytes[..end])
+}
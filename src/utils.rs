@@ -1,57 +1,39 @@
+use crate::text::{decodes_to, encode_windows_1252};
 use crate::{Result, SgImageError};
-use std::io::Read;
-use std::str;
+use alloc::vec;
+use alloc::vec::Vec;
 
 pub trait ReadHelper {
-    fn read_u8(&mut self) -> Result<u8>;
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()>;
 
-    fn read_u16_le(&mut self) -> Result<u16>;
-
-    fn read_u32_le(&mut self) -> Result<u32>;
-
-    fn read_i32_le(&mut self) -> Result<i32>;
-
-    fn read_utf(&mut self, length: usize) -> Result<String>;
-
-    fn read_bytes<const LENGTH: usize>(&mut self) -> Result<[u8; LENGTH]>
-    where
-        [u8; LENGTH]: Default;
-}
-
-impl<R: Read> ReadHelper for R {
     fn read_u8(&mut self) -> Result<u8> {
         let mut tmp = [0; 1];
-        self.read_exact(&mut tmp)?;
+        self.read_into(&mut tmp)?;
         Ok(tmp[0])
     }
 
     fn read_u16_le(&mut self) -> Result<u16> {
         let mut tmp = [0; 2];
-        self.read_exact(&mut tmp)?;
+        self.read_into(&mut tmp)?;
         Ok(u16::from_le_bytes(tmp))
     }
 
     fn read_u32_le(&mut self) -> Result<u32> {
         let mut tmp = [0; 4];
-        self.read_exact(&mut tmp)?;
+        self.read_into(&mut tmp)?;
         Ok(u32::from_le_bytes(tmp))
     }
 
     fn read_i32_le(&mut self) -> Result<i32> {
         let mut tmp = [0; 4];
-        self.read_exact(&mut tmp)?;
+        self.read_into(&mut tmp)?;
         Ok(i32::from_le_bytes(tmp))
     }
 
-    fn read_utf(&mut self, length: usize) -> Result<String> {
+    fn read_vec(&mut self, length: usize) -> Result<Vec<u8>> {
         let mut tmp = vec![0; length];
-
-        self.read_exact(&mut tmp)?;
-
-        return match str::from_utf8(&tmp) {
-            Ok(str) => Ok(String::from(str.trim_end_matches(char::from(0)))),
-            Err(err) => Err(SgImageError::Utf8Error(err)),
-        };
+        self.read_into(&mut tmp)?;
+        Ok(tmp)
     }
 
     fn read_bytes<const LENGTH: usize>(&mut self) -> Result<[u8; LENGTH]>
@@ -59,7 +41,86 @@ impl<R: Read> ReadHelper for R {
         [u8; LENGTH]: Default,
     {
         let mut result: [u8; LENGTH] = Default::default();
-        self.read_exact(&mut result)?;
+        self.read_into(&mut result)?;
         Ok(result)
     }
 }
+
+impl ReadHelper for &[u8] {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        if self.len() < buf.len() {
+            return Err(SgImageError::UnexpectedEof);
+        }
+
+        let (head, tail) = self.split_at(buf.len());
+        buf.copy_from_slice(head);
+        *self = tail;
+        Ok(())
+    }
+}
+
+/// Reports running out of data as [SgImageError::UnexpectedEof], the same as reading from a slice.
+#[cfg(feature = "std")]
+impl<R: std::io::Read> ReadHelper for std::io::BufReader<R> {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        use std::io::Read;
+
+        match self.read_exact(buf) {
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Err(SgImageError::UnexpectedEof),
+            result => Ok(result?),
+        }
+    }
+}
+
+pub trait WriteHelper {
+    fn write_from(&mut self, buf: &[u8]);
+
+    fn write_u8(&mut self, value: u8) {
+        self.write_from(&[value]);
+    }
+
+    fn write_u16_le(&mut self, value: u16) {
+        self.write_from(&value.to_le_bytes());
+    }
+
+    fn write_u32_le(&mut self, value: u32) {
+        self.write_from(&value.to_le_bytes());
+    }
+
+    fn write_i32_le(&mut self, value: i32) {
+        self.write_from(&value.to_le_bytes());
+    }
+
+    /// Write the raw bytes of a string field if they still decode to the string, or the string encoded as Windows-1252 otherwise
+    /// (e.g. after the string was changed), padded with zeroes (or truncated) to the given length.
+    fn write_string(&mut self, value: &str, raw_bytes: &[u8], length: usize) {
+        let mut tmp = vec![0; length];
+        let encoded;
+        let bytes = if decodes_to(raw_bytes, value) {
+            raw_bytes
+        } else {
+            encoded = encode_windows_1252(value);
+            &encoded[..]
+        };
+        let count = bytes.len().min(length);
+        tmp[..count].copy_from_slice(&bytes[..count]);
+        self.write_from(&tmp);
+    }
+}
+
+impl WriteHelper for Vec<u8> {
+    fn write_from(&mut self, buf: &[u8]) {
+        self.extend_from_slice(buf);
+    }
+}
+
+/// Advance the slice by the given number of bytes.
+pub fn skip_bytes(data: &mut &[u8], count: usize) -> Result<()> {
+    match data.get(count..) {
+        Some(remaining) => {
+            *data = remaining;
+            Ok(())
+        }
+        None => Err(SgImageError::UnexpectedEof),
+    }
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "image")]
+
+use sg_image_reader::{SgBitmapMetadata, SgFileMetadata, SgImageError, SgImageMetadata};
+use std::fs;
+use std::path::PathBuf;
+
+/// Temporary folder with the files of the exported sg file, removed when dropped.
+struct TempFolder(PathBuf);
+
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Get the 555 colour and the RGBA pixel it decodes to for the given seed.
+fn colour(seed: usize) -> (u16, [u8; 4]) {
+    let (r, g, b) = ((seed * 7 % 32) as u16, (seed * 13 % 32) as u16, (seed * 3 % 32) as u16);
+    ((r << 10) | (g << 5) | b, [(r << 3) as u8, (g << 3) as u8, (b << 3) as u8, 255])
+}
+
+/// Write the .555 file of plain images with the given sizes, returning metadata of the sg file and the expected pixels of the images.
+fn write_plain_images(folder: &TempFolder, sizes: &[(u16, u16)]) -> (SgFileMetadata, Vec<Vec<u8>>) {
+    let mut data_555 = Vec::new();
+    let mut images = vec![SgImageMetadata::default()];
+    let mut expected = vec![Vec::new()];
+
+    for (index, (width, height)) in sizes.iter().enumerate() {
+        let offset = data_555.len() as u32;
+        let mut pixels = Vec::new();
+
+        for position in 0..(*width as usize * *height as usize) {
+            let (colour, pixel) = colour(index * 50 + position);
+            data_555.extend_from_slice(&colour.to_le_bytes());
+            pixels.extend_from_slice(&pixel);
+        }
+
+        images.push(SgImageMetadata {
+            id: index as u32 + 1,
+            offset,
+            length: data_555.len() as u32 - offset,
+            width: *width,
+            height: *height,
+            ..SgImageMetadata::default()
+        });
+        expected.push(pixels);
+    }
+
+    fs::write(folder.0.join("Assets.555"), data_555).unwrap();
+
+    let bitmap = SgBitmapMetadata {
+        external_filename: String::from("Assets.bmp"),
+        comment: String::from("Fixture"),
+        num_images: sizes.len() as u32,
+        start_index: 1,
+        end_index: sizes.len() as u32,
+        ..SgBitmapMetadata::default()
+    };
+
+    let sg_file = SgFileMetadata {
+        folder: String::from(folder.0.to_str().unwrap()),
+        filename: String::from("Assets.sg3"),
+        version: 0xd5,
+        bitmaps: vec![bitmap],
+        raw_images: images.clone(),
+        images,
+        ..SgFileMetadata::default()
+    };
+
+    (sg_file, expected)
+}
+
+#[test]
+fn exports_bitmap_assets() {
+    let folder = TempFolder(std::env::temp_dir().join(format!("sg_image_reader_assets_{}", std::process::id())));
+    fs::create_dir_all(&folder.0).unwrap();
+    let (mut sg_file, expected) = write_plain_images(&folder, &[(3, 2), (4, 3), (2, 2), (5, 5), (6, 1)]);
+
+    // Image 2 is animated by the next three images, one of which has no pixels
+    sg_file.images[2].anim_sprites = 3;
+    sg_file.images[2].anim_speed_id = 2;
+    sg_file.images[3].width = 0;
+    sg_file.images[4].x_offset = 7;
+    sg_file.images[4].y_offset = 1;
+
+    let assets_folder = folder.0.join("assets");
+    sg_file.export_bitmap_assets(0, &assets_folder).unwrap();
+
+    let xml = fs::read_to_string(assets_folder.join("Fixture/Fixture.xml")).unwrap();
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\"?>\n<!DOCTYPE assetlist>\n<assetlist name=\"Fixture\">\n\
+         \t<image id=\"Fixture_1\" src=\"Fixture_1\" width=\"3\" height=\"2\"/>\n\
+         \t<image id=\"Fixture_2\" src=\"Fixture_2\" width=\"4\" height=\"3\">\n\
+         \t\t<animation frames=\"2\" speed=\"2\" reversible=\"false\" x=\"7\" y=\"1\">\n\
+         \t\t\t<frame src=\"Fixture_4\"/>\n\
+         \t\t\t<frame src=\"Fixture_5\"/>\n\
+         \t\t</animation>\n\t</image>\n\
+         </assetlist>\n"
+    );
+
+    let mut files: Vec<String> = fs::read_dir(assets_folder.join("Fixture")).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    files.sort();
+    assert_eq!(files, ["Fixture.xml", "Fixture_1.png", "Fixture_2.png", "Fixture_4.png", "Fixture_5.png"]);
+
+    for (position, index) in [(1, 1), (2, 2), (4, 4), (5, 5)] {
+        let png = image::open(assets_folder.join(format!("Fixture/Fixture_{}.png", position))).unwrap().to_rgba8();
+        assert_eq!(png.dimensions(), (sg_file.images[index].width as u32, sg_file.images[index].height as u32));
+        assert_eq!(png.into_raw(), expected[index]);
+    }
+
+    let err = sg_file.export_bitmap_assets(1, &assets_folder).unwrap_err();
+    assert!(matches!(err, SgImageError::InvalidBitmapId(1)));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::*;
+use sg_image_reader::{SgFileMetadata, VecImageBuilderFactory};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+#[tokio::test]
+async fn loads_metadata_and_images_from_async_readers() {
+    let sprite = sprite_image(7, 5);
+    let images = [plain_image(4, 3), sprite.clone(), mirrored_image(&sprite, 1), with_alpha_mask(sprite_image(6, 8))];
+    let fixture = write_fixture("async", 0xd6, &images);
+    let expected = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+    // The whole file is read, whatever the position of the reader
+    let mut file = File::open(&fixture.sg_path).await.unwrap();
+    file.read_exact(&mut [0; 100]).await.unwrap();
+
+    let sg_file = SgFileMetadata::load_metadata_from_async_reader(&mut file, expected.folder.clone(), expected.filename.clone()).await.unwrap();
+
+    assert_eq!(sg_file, expected);
+
+    let mut file = File::open(sg_file.get_555_file_path(0, false)).await.unwrap();
+
+    for (index, image) in sg_file.images.iter().enumerate() {
+        let pixels = image.load_image_async(&mut file, &VecImageBuilderFactory).await.unwrap();
+
+        assert!(pixels == fixture.expected[index], "pixels of image {} differ", index);
+    }
+}
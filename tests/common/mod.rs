@@ -0,0 +1,344 @@
+//! Generator of small synthetic sg files, along with the pixels they are expected to decode to.
+//!
+//! The files are encoded here independently of the library, following the format description.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+pub const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// Image to be written to a fixture file.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureImage {
+    pub image_type: u16,
+    pub width: u16,
+    pub height: u16,
+    pub uncompressed_length: u32,
+    pub invert_offset: i32,
+    pub isometric_size: u8,
+    /// Pixel data, as stored in the .555 file.
+    pub data: Vec<u8>,
+    /// Alpha mask, as stored in the .555 file.
+    pub alpha: Vec<u8>,
+    /// RGBA pixels the image is expected to decode to.
+    pub expected: Vec<u8>,
+}
+
+/// Sg file written to a temporary folder, with its .555 file next to it.
+pub struct Fixture {
+    pub sg_path: PathBuf,
+    /// Expected RGBA pixels of all the images, including the empty first image.
+    pub expected: Vec<Vec<u8>>,
+}
+
+/// Remove the temporary folder of the fixture along with everything written to it.
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        if let Some(folder) = self.sg_path.parent() {
+            let _ = fs::remove_dir_all(folder);
+        }
+    }
+}
+
+/// Get the 555 colour and the RGBA pixel it decodes to for the given seed.
+pub fn colour(seed: usize) -> (u16, [u8; 4]) {
+    let r = (seed * 7 % 32) as u16;
+    let g = (seed * 13 % 32) as u16;
+    let b = (seed * 3 % 32) as u16;
+
+    // Avoid the colour marking transparent pixels
+    let g = if r == 31 && g == 0 && b == 31 { 1 } else { g };
+
+    ((r << 10) | (g << 5) | b, [(r << 3) as u8, (g << 3) as u8, (b << 3) as u8, 255])
+}
+
+/// Plain image, with every fifth pixel transparent.
+pub fn plain_image(width: u16, height: u16) -> FixtureImage {
+    let mut image = FixtureImage {
+        width,
+        height,
+        ..FixtureImage::default()
+    };
+
+    for position in 0..width as usize * height as usize {
+        if position % 5 == 4 {
+            push_u16(&mut image.data, 0xf81f);
+            image.expected.extend_from_slice(&TRANSPARENT);
+        } else {
+            let (colour, pixel) = colour(position);
+            push_u16(&mut image.data, colour);
+            image.expected.extend_from_slice(&pixel);
+        }
+    }
+
+    image.uncompressed_length = image.data.len() as u32;
+    image
+}
+
+/// Sprite image, with a transparent stripe down the left side and in the middle row.
+pub fn sprite_image(width: u16, height: u16) -> FixtureImage {
+    let mut image = FixtureImage {
+        image_type: 256,
+        width,
+        height,
+        ..FixtureImage::default()
+    };
+
+    let pixels: Vec<Option<u16>> = (0..width as usize * height as usize)
+        .map(|position| {
+            let (x, y) = (position % width as usize, position / width as usize);
+            if x < 2 || y == height as usize / 2 {
+                None
+            } else {
+                Some(colour(position).0)
+            }
+        })
+        .collect();
+
+    for pixel in &pixels {
+        image.expected.extend_from_slice(&pixel.map_or(TRANSPARENT, |colour| rgba(colour, 255)));
+    }
+
+    image.data = encode_runs(&pixels, push_u16);
+    image
+}
+
+/// Isometric image of the given size in tiles, with small (58x30) or large (78x40) tiles, and a top part of the given height.
+///
+/// Each tile of the base is filled with a single colour and the top part with a pattern.
+pub fn isometric_image(size: u16, large_tiles: bool, top_height: u16) -> FixtureImage {
+    let (tile_width, tile_height) = if large_tiles { (78, 40) } else { (58, 30) };
+    let width = size * (tile_width + 2) - 2;
+    let base_height = size * tile_height;
+    let height = base_height + top_height;
+
+    let mut image = FixtureImage {
+        image_type: 30,
+        width,
+        height,
+        isometric_size: size as u8,
+        ..FixtureImage::default()
+    };
+
+    let mut pixels: Vec<Option<u16>> = vec![None; width as usize * height as usize];
+
+    // Tiles are stored row by row, starting from the top corner of the footprint
+    let mut tile = 0;
+    for row in 0..(2 * size - 1) {
+        let tiles_in_row = if row < size { row + 1 } else { 2 * size - 1 - row };
+        let x_start = (size - 1).abs_diff(row) * tile_height;
+        let y_start = top_height + row * tile_height / 2;
+
+        for i in 0..tiles_in_row {
+            let (tile_colour, _) = colour(tile * 11 + 1);
+            tile += 1;
+
+            for y in 0..tile_height {
+                let (from, to) = if y < tile_height / 2 {
+                    (tile_height - 2 - 2 * y, tile_height + 2 * y)
+                } else {
+                    let k = y - tile_height / 2;
+                    (2 * k, tile_width - 2 * k)
+                };
+
+                for x in from..to {
+                    let position = (y_start + y) as usize * width as usize + (x_start + i * (tile_width + 2) + x) as usize;
+                    push_u16(&mut image.data, tile_colour);
+                    pixels[position] = Some(tile_colour);
+                }
+            }
+        }
+    }
+
+    image.uncompressed_length = image.data.len() as u32;
+
+    // Top part covers the whole image, skipping over the base
+    let top: Vec<Option<u16>> = (0..pixels.len())
+        .map(|position| {
+            let (x, y) = (position % width as usize, position / width as usize);
+            if y < top_height as usize && x % 3 != 0 {
+                Some(colour(position).0)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (pixel, top_pixel) in pixels.iter_mut().zip(top.iter()) {
+        if top_pixel.is_some() {
+            *pixel = *top_pixel;
+        }
+    }
+
+    image.data.extend(encode_runs(&top, push_u16));
+
+    for pixel in &pixels {
+        image.expected.extend_from_slice(&pixel.map_or(TRANSPARENT, |colour| rgba(colour, 255)));
+    }
+
+    image
+}
+
+/// Add an alpha mask fading out the visible pixels of the image row by row.
+pub fn with_alpha_mask(mut image: FixtureImage) -> FixtureImage {
+    let width = image.width as usize;
+
+    let alpha: Vec<Option<u8>> = image
+        .expected
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(position, pixel)| if pixel[3] == 0 { None } else { Some((31 - (position / width) % 32) as u8) })
+        .collect();
+
+    for (pixel, alpha) in image.expected.chunks_exact_mut(4).zip(alpha.iter()) {
+        if let Some(alpha) = alpha {
+            pixel[3] = alpha << 3;
+        }
+    }
+
+    image.alpha = encode_runs(&alpha, |data, alpha| data.push(alpha));
+    image
+}
+
+/// Record of an image mirroring the image the given number of records before it.
+pub fn mirrored_image(source: &FixtureImage, records_back: i32) -> FixtureImage {
+    let mut expected = Vec::with_capacity(source.expected.len());
+    for row in source.expected.chunks_exact(source.width as usize * 4) {
+        for pixel in row.chunks_exact(4).rev() {
+            expected.extend_from_slice(pixel);
+        }
+    }
+
+    FixtureImage {
+        image_type: source.image_type,
+        width: source.width,
+        height: source.height,
+        invert_offset: -records_back,
+        expected,
+        ..FixtureImage::default()
+    }
+}
+
+/// Write a sg file of the given version with the images in a single bitmap, and a .555 file with their pixel data.
+pub fn write_fixture(name: &str, version: u32, images: &[FixtureImage]) -> Fixture {
+    let folder = std::env::temp_dir().join(format!("sg_image_reader_{}_{:x}_{}", name, version, std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+
+    let include_alpha = version >= 0xd6;
+    let max_bitmap_records = if version == 0xd3 { 100 } else { 200 };
+    let image_record_size = if include_alpha { 72 } else { 64 };
+
+    let mut data_555 = Vec::new();
+    let mut image_records = vec![0; image_record_size];
+
+    for image in images {
+        let offset = data_555.len() as u32;
+        let (data, alpha) = if image.invert_offset == 0 {
+            (&image.data[..], if include_alpha { &image.alpha[..] } else { &[][..] })
+        } else {
+            (&[][..], &[][..])
+        };
+        data_555.extend_from_slice(data);
+        data_555.extend_from_slice(alpha);
+
+        let record = &mut image_records;
+        push_u32(record, if image.invert_offset == 0 { offset } else { 0 });
+        push_u32(record, data.len() as u32);
+        push_u32(record, image.uncompressed_length);
+        push_u32(record, 0);
+        push_u32(record, image.invert_offset as u32);
+        push_u16(record, image.width);
+        push_u16(record, image.height);
+        record.extend_from_slice(&[0; 6]);
+        push_u16(record, 0); // animation sprites
+        push_u16(record, 0);
+        push_u16(record, 0); // x offset
+        push_u16(record, 0); // y offset
+        record.extend_from_slice(&[0; 10]);
+        record.push(0); // is reversible
+        record.push(0);
+        push_u16(record, image.image_type);
+        record.extend_from_slice(&[0, 0, 0, image.isometric_size]);
+        record.push(0); // bitmap id
+        record.push(0);
+        record.push(0); // animation speed
+        record.extend_from_slice(&[0; 5]);
+        if include_alpha {
+            push_u32(record, if alpha.is_empty() { 0 } else { offset + data.len() as u32 });
+            push_u32(record, alpha.len() as u32);
+        }
+    }
+
+    let sg_file_size = 680 + 200 * max_bitmap_records + image_records.len();
+
+    let mut sg = Vec::with_capacity(sg_file_size);
+    push_u32(&mut sg, if version == 0xd3 { 74480 } else { sg_file_size as u32 });
+    push_u32(&mut sg, version);
+    push_u32(&mut sg, 0);
+    push_u32(&mut sg, images.len() as u32 + 1);
+    push_u32(&mut sg, images.len() as u32);
+    push_u32(&mut sg, 1);
+    push_u32(&mut sg, 1);
+    push_u32(&mut sg, data_555.len() as u32);
+    push_u32(&mut sg, data_555.len() as u32);
+    push_u32(&mut sg, 0);
+    sg.resize(680, 0);
+
+    // The only bitmap
+    let mut name_bytes = format!("{}.bmp", name).into_bytes();
+    name_bytes.resize(65, 0);
+    sg.extend_from_slice(&name_bytes);
+    let mut comment = b"Fixture".to_vec();
+    comment.resize(51, 0);
+    sg.extend_from_slice(&comment);
+    for value in [0, 0, images.len() as u32, 1, images.len() as u32, 0, 0, 0, 0, 0, 0, 0, data_555.len() as u32, data_555.len() as u32, 0] {
+        push_u32(&mut sg, value);
+    }
+    sg.extend_from_slice(&[0; 24]);
+    sg.resize(680 + 200 * max_bitmap_records, 0);
+
+    sg.extend_from_slice(&image_records);
+
+    let sg_path = folder.join(format!("{}.sg3", name));
+    fs::write(&sg_path, sg).unwrap();
+    fs::write(folder.join(format!("{}.555", name)), data_555).unwrap();
+
+    let mut expected = vec![Vec::new()];
+    expected.extend(images.iter().map(|image| image.expected.clone()));
+
+    Fixture { sg_path, expected }
+}
+
+fn rgba(colour: u16, alpha: u8) -> [u8; 4] {
+    [((colour >> 10) << 3) as u8, (((colour >> 5) & 0x1f) << 3) as u8, ((colour & 0x1f) << 3) as u8, alpha]
+}
+
+/// Run-length encode the values, runs of missing values are skipped.
+fn encode_runs<T: Copy, W: Fn(&mut Vec<u8>, T)>(values: &[Option<T>], write: W) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for run in values.chunk_by(|a, b| a.is_some() == b.is_some()) {
+        for chunk in run.chunks(100) {
+            if chunk[0].is_some() {
+                data.push(chunk.len() as u8);
+                for value in chunk {
+                    write(&mut data, value.unwrap());
+                }
+            } else {
+                data.push(255);
+                data.push(chunk.len() as u8);
+            }
+        }
+    }
+
+    data
+}
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
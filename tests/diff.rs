@@ -0,0 +1,77 @@
+mod common;
+
+use common::*;
+use sg_image_reader::{BitmapChange, SgFileMetadata, VecImageBuilderFactory};
+
+fn load(fixture: &Fixture) -> (SgFileMetadata, Vec<Vec<u8>>) {
+    SgFileMetadata::load_fully(&fixture.sg_path, &VecImageBuilderFactory).unwrap()
+}
+
+#[test]
+fn finds_no_differences_in_same_files() {
+    let fixture = write_fixture("same", 0xd5, &[plain_image(3, 2), sprite_image(4, 3)]);
+    let (sg_file, pixels) = load(&fixture);
+
+    let diff = sg_file.diff_with_pixels(&pixels, &sg_file, &pixels);
+
+    assert!(diff.is_empty());
+    assert_eq!(diff.matched_images, vec![(1, 1), (2, 2)]);
+}
+
+#[test]
+fn finds_changed_images() {
+    let old = write_fixture("changed", 0xd5, &[plain_image(3, 2), sprite_image(4, 3), sprite_image(5, 5)]);
+    let new = write_fixture("changed", 0xd6, &[plain_image(3, 2), with_alpha_mask(sprite_image(4, 3)), sprite_image(5, 5), plain_image(2, 2)]);
+    let (old, old_pixels) = load(&old);
+    let (new, new_pixels) = load(&new);
+
+    let diff = old.diff_with_pixels(&old_pixels, &new, &new_pixels);
+
+    assert!(diff.bitmaps.is_empty());
+    assert_eq!(diff.added_images, vec![4]);
+    assert!(diff.removed_images.is_empty());
+
+    let changed_fields: Vec<(usize, Vec<&str>)> = diff.changed_images.iter().map(|change| (change.old, change.fields.iter().map(|field| field.field).collect())).collect();
+    assert_eq!(changed_fields, vec![(2, vec!["alpha_offset", "alpha_length"]), (3, vec!["offset"])]);
+
+    assert_eq!(diff.changed_pixels, vec![(2, 2)]);
+}
+
+#[test]
+fn finds_changed_bitmaps() {
+    let fixture = write_fixture("old_bitmap", 0xd5, &[plain_image(3, 2)]);
+    let old = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let mut new = old.clone();
+    new.bitmaps.insert(0, new.bitmaps[0].clone());
+    new.bitmaps[0].external_filename = String::from("new_bitmap.bmp");
+    new.raw_images[1].bitmap_id = 1;
+
+    let diff = old.diff(&new);
+
+    assert_eq!(
+        diff.bitmaps,
+        vec![
+            BitmapChange::Moved { old: 0, new: 1, name: String::from("old_bitmap.bmp") },
+            BitmapChange::Added { new: 0, name: String::from("new_bitmap.bmp") },
+        ]
+    );
+    assert_eq!(diff.changed_images.len(), 1);
+    assert_eq!(diff.changed_images[0].fields[0].field, "bitmap_id");
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn builds_visual_diffs() {
+    let old = write_fixture("visual", 0xd5, &[sprite_image(4, 3)]);
+    let new = write_fixture("visual", 0xd6, &[with_alpha_mask(sprite_image(4, 3))]);
+    let (old, old_pixels) = load(&old);
+    let (new, new_pixels) = load(&new);
+
+    let diff_image = sg_image_reader::SgFileDiff::visual_diff(&old.images[1], &old_pixels[1], &new.images[1], &new_pixels[1]);
+
+    assert_eq!(diff_image.dimensions(), (12, 3));
+    // Transparent pixels on the left side are the same, the visible ones got faded by the alpha mask
+    assert_eq!(diff_image.get_pixel(8, 0).0, [0, 0, 0, 0]);
+    assert_eq!(diff_image.get_pixel(10, 0).0, [255, 0, 0, 255]);
+    assert_eq!(diff_image.get_pixel(6, 0).0, new_pixels[1][8..12]);
+}
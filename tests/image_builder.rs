@@ -0,0 +1,18 @@
+use sg_image_reader::*;
+
+#[test]
+fn builds_images_with_all_the_traits_in_scope() {
+    let mut builder = VecImageBuilderFactory.new_builder(2, 1);
+    builder.set_pixel_by_pos(0, [1, 2, 3, 255]);
+    builder.set_pixel_by_pos(1, [4, 5, 6, 255]);
+    builder.set_alpha(1, 128);
+    builder.flip_horizontal();
+
+    assert_eq!(builder.build(), [4, 5, 6, 128, 1, 2, 3, 255]);
+
+    let mut builder = VecImageBuilderFactory.new_builder(1, 1);
+    builder.try_set_pixel_by_pos(0, [7, 8, 9, 255]);
+    builder.try_flip_horizontal();
+
+    assert_eq!(builder.try_build(), Ok(vec![7, 8, 9, 255]));
+}
@@ -0,0 +1,79 @@
+use sg_image_reader::{GroupNames, SgFileMetadata, SgImageMetadata};
+
+const HEADER: &str = "#ifndef GRAPHICS_IMAGE_GROUP_H
+#define GRAPHICS_IMAGE_GROUP_H
+
+#define GROUP_FIRST 1
+#define GROUP_SECOND 2
+#define GROUP_ALIAS_OF_SECOND 2
+#define GROUP_SECOND_ALIAS 3
+#define GROUP_LAST 5
+#define GROUP_INVALID x
+
+#endif // GRAPHICS_IMAGE_GROUP_H
+";
+
+/// Metadata of a file with images 1 to 5, with group 1 starting at image 1, groups 2 and 3 at image 3 and group 5 at image 4.
+fn grouped_metadata() -> SgFileMetadata {
+    let mut group_image_ids = vec![0; 300];
+    group_image_ids[1] = 1;
+    group_image_ids[2] = 3;
+    group_image_ids[3] = 3;
+    group_image_ids[5] = 4;
+
+    SgFileMetadata {
+        group_image_ids,
+        images: vec![SgImageMetadata::default(); 6],
+        ..SgFileMetadata::default()
+    }
+}
+
+#[test]
+fn parses_group_names() {
+    let group_names = GroupNames::parse_c_header(HEADER);
+
+    assert_eq!(group_names.group("GROUP_FIRST"), Some(1));
+    assert_eq!(group_names.group("GROUP_LAST"), Some(5));
+    assert_eq!(group_names.group("GROUP_INVALID"), None);
+    assert_eq!(group_names.group("GRAPHICS_IMAGE_GROUP_H"), None);
+    assert_eq!(group_names.group("GROUP_ALIAS_OF_SECOND"), Some(2));
+    // Names defined first are used for groups with several names
+    assert_eq!(group_names.name(2), Some("GROUP_SECOND"));
+    assert_eq!(group_names.name(3), Some("GROUP_SECOND_ALIAS"));
+    assert_eq!(group_names.name(4), None);
+}
+
+#[test]
+fn finds_images_of_groups() {
+    let sg_file = grouped_metadata();
+
+    assert_eq!(sg_file.group_image_index(1, 0), Some(1));
+    assert_eq!(sg_file.group_image_index(2, 1), Some(4));
+    assert_eq!(sg_file.group_image_index(5, 1), Some(5));
+    assert_eq!(sg_file.group_image_index(5, 2), None);
+    assert_eq!(sg_file.group_image_index(300, 0), None);
+
+    let group_names = GroupNames::parse_c_header(HEADER);
+    assert_eq!(sg_file.named_group_image_index(&group_names, "GROUP_SECOND_ALIAS", 0), Some(3));
+    assert_eq!(sg_file.named_group_image_index(&group_names, "GROUP_LAST", 1), Some(5));
+    assert_eq!(sg_file.named_group_image_index(&group_names, "GROUP_MISSING", 0), None);
+}
+
+#[test]
+fn finds_groups_of_images() {
+    let sg_file = grouped_metadata();
+
+    // Groups left at image 0 only contain the images before the first group
+    assert_eq!(sg_file.image_group(0), Some((0, 0)));
+    assert_eq!(sg_file.image_group(1), Some((1, 0)));
+    assert_eq!(sg_file.image_group(2), Some((1, 1)));
+    // Groups 2 and 3 start at the same image
+    assert_eq!(sg_file.image_group(3), Some((2, 0)));
+    assert_eq!(sg_file.image_group(5), Some((5, 1)));
+    assert_eq!(sg_file.image_group(6), None);
+
+    for index in 1..sg_file.images.len() {
+        let (group, offset) = sg_file.image_group(index).unwrap();
+        assert_eq!(sg_file.group_image_index(group, offset), Some(index));
+    }
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "import")]
+
+use image::ColorType;
+use sg_image_reader::{ImportManifest, SgFileMetadata, SgImageError, VecImageBuilderFactory};
+use std::fs;
+use std::path::PathBuf;
+
+/// RGBA pixels of a PNG file to import.
+struct Png {
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+
+impl Png {
+    /// Image with colours representable in 555 format, leaving pixels out where `alpha` returns 0.
+    fn new(width: u16, height: u16, alpha: impl Fn(usize, usize) -> u8) -> Self {
+        let mut pixels = Vec::new();
+
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let seed = y * width as usize + x;
+                let pixel = match alpha(x, y) {
+                    0 => [0, 0, 0, 0],
+                    alpha => [(seed * 7 % 32) as u8 * 8, (seed * 13 % 32) as u8 * 8, (seed * 3 % 32) as u8 * 8, alpha],
+                };
+                pixels.extend_from_slice(&pixel);
+            }
+        }
+
+        Png { width, height, pixels }
+    }
+
+    /// Pixels of the image mirrored horizontally.
+    fn mirrored(&self) -> Vec<u8> {
+        self.pixels.chunks_exact(self.width as usize * 4).flat_map(|row| row.chunks_exact(4).rev().flatten().copied()).collect()
+    }
+}
+
+/// Temporary folder with PNG files to import, removed when dropped.
+struct PngFolder(PathBuf);
+
+impl PngFolder {
+    fn new(name: &str, images: &[(&str, &Png)]) -> Self {
+        let folder = std::env::temp_dir().join(format!("sg_image_reader_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        for (file, image) in images {
+            image::save_buffer(folder.join(file), &image.pixels, image.width as u32, image.height as u32, ColorType::Rgba8).unwrap();
+        }
+
+        PngFolder(folder)
+    }
+}
+
+impl Drop for PngFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+const MANIFEST: &str = r#"{
+    "version": 214,
+    "group_image_ids": [0, 1, 4],
+    "bitmaps": [
+        {
+            "external_filename": "Imported.bmp",
+            "comment": "Imported",
+            "images": [
+                { "file": "plain.png" },
+                { "file": "sprite.png", "image_type": 256, "x_offset": 3, "anim_sprites": 1, "anim_speed_id": 2, "is_reversible": true },
+                { "image_type": 256, "mirror_of": 2 }
+            ]
+        },
+        {
+            "external_filename": "Alpha.bmp",
+            "images": [
+                { "file": "alpha.png", "image_type": 256 },
+                { "mirror_of": 4 },
+                { "file": "isometric.png", "image_type": 30 },
+                {},
+                { "image_type": 256, "mirror_of": 2, "x_offset": 5, "y_offset": 6, "anim_sprites": 2, "anim_speed_id": 3 }
+            ]
+        }
+    ]
+}"#;
+
+#[test]
+fn imports_png_files_described_by_manifest() {
+    let plain = Png::new(4, 3, |x, y| if (y * 4 + x) % 5 == 4 { 0 } else { 255 });
+    let sprite = Png::new(7, 5, |x, y| if (x + y) % 4 == 0 { 0 } else { 255 });
+    let alpha = Png::new(6, 8, |x, y| if x == y { 0 } else { (31 - y as u8) << 3 });
+    let isometric = Png::new(58, 42, |_, _| 255);
+    let folder = PngFolder::new("import", &[("plain.png", &plain), ("sprite.png", &sprite), ("alpha.png", &alpha), ("isometric.png", &isometric)]);
+
+    let manifest = ImportManifest::from_json(MANIFEST).unwrap();
+    assert_eq!(manifest.version, 0xd6);
+    assert_eq!(manifest.bitmaps[1].comment, "");
+    assert_eq!(manifest.bitmaps[1].images[1].mirror_of, Some(4));
+    assert_eq!(manifest.bitmaps[1].images[3].file, None);
+
+    let sg_path = folder.0.join("Imported.sg3");
+    let imported = SgFileMetadata::import(&manifest, &folder.0, &sg_path).unwrap();
+
+    assert!(sg_path.exists());
+    assert!(folder.0.join("Imported.555").exists());
+
+    let (sg_file, pixels) = SgFileMetadata::load_fully(&sg_path, &VecImageBuilderFactory).unwrap();
+
+    assert_eq!(sg_file.raw_images, imported.raw_images);
+    assert_eq!(sg_file.images, imported.images);
+    assert_eq!(&sg_file.group_image_ids[..4], [0, 1, 4, 0]);
+    assert_eq!(sg_file.bitmaps.len(), 2);
+    assert_eq!((sg_file.bitmaps[0].start_index, sg_file.bitmaps[0].end_index), (1, 3));
+    assert_eq!((sg_file.bitmaps[1].start_index, sg_file.bitmaps[1].end_index), (4, 8));
+    assert_eq!(sg_file.bitmaps[1].external_filename, "Alpha.bmp");
+    assert!(sg_file.lint().is_empty(), "{:?}", sg_file.lint());
+
+    // Mirrored images refer back to their source and share its pixel data, including the alpha mask
+    assert_eq!(sg_file.raw_images[3].invert_offset, -1);
+    assert_eq!(sg_file.raw_images[5].invert_offset, -1);
+    assert!(sg_file.images[4].alpha_length > 0);
+    assert_eq!(sg_file.images[5].alpha_length, sg_file.images[4].alpha_length);
+    assert_eq!((sg_file.images[2].x_offset, sg_file.images[2].anim_sprites, sg_file.images[2].is_reversible), (3, 1, 1));
+    assert_eq!((sg_file.images[6].width, sg_file.images[6].height, sg_file.images[6].isometric_size()), (58, 42, Some(1)));
+
+    // Mirrored copies in other bitmaps keep the rest of their own record
+    let mirror = &sg_file.raw_images[8];
+    assert_eq!((mirror.invert_offset, mirror.bitmap_id, mirror.offset, mirror.length), (-6, 1, sg_file.raw_images[2].offset, sg_file.raw_images[2].length));
+    assert_eq!((mirror.x_offset, mirror.y_offset, mirror.anim_sprites, mirror.anim_speed_id, mirror.is_reversible), (5, 6, 2, 3, 0));
+
+    // The isometric image is left out, as its pixels outside of the footprint and the part above it are not stored
+    let expected = [(1, plain.pixels), (2, sprite.pixels.clone()), (3, sprite.mirrored()), (4, alpha.pixels.clone()), (5, alpha.mirrored()), (7, Vec::new()), (8, sprite.mirrored())];
+    assert_eq!(pixels.len(), 9);
+    for (index, expected) in expected {
+        assert!(pixels[index] == expected, "pixels of image {} differ", index);
+    }
+}
+
+#[test]
+fn rejects_invalid_manifests() {
+    let folder = PngFolder::new("import_invalid", &[]);
+    let sg_path = folder.0.join("Invalid.sg3");
+
+    let err = ImportManifest::from_json("{ \"version\": \"d6\" }").unwrap_err();
+    assert!(matches!(err, SgImageError::InvalidManifest(_)));
+
+    let manifest = ImportManifest::from_json(r#"{ "bitmaps": [{ "images": [{ "mirror_of": 1 }] }] }"#).unwrap();
+    let err = SgFileMetadata::import(&manifest, &folder.0, &sg_path).unwrap_err();
+    assert_eq!(err.to_string(), "invalid import manifest: image 1 mirrors image 1 which is not defined before it");
+
+    let manifest = ImportManifest::from_json(r#"{ "version": 212 }"#).unwrap();
+    let err = SgFileMetadata::import(&manifest, &folder.0, &sg_path).unwrap_err();
+    assert!(matches!(err, SgImageError::UnsupportedVersion(0xd4)));
+    assert!(!sg_path.exists());
+}
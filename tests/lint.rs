@@ -0,0 +1,80 @@
+mod common;
+
+use common::*;
+use sg_image_reader::{BitmapIndexField, DataSizeField, LintFinding, SgFileMetadata};
+
+fn fixture_metadata(name: &str) -> SgFileMetadata {
+    let sprite = sprite_image(4, 3);
+    let images = [plain_image(3, 2), sprite.clone(), mirrored_image(&sprite, 1), with_alpha_mask(sprite_image(5, 5))];
+    let fixture = write_fixture(name, 0xd6, &images);
+
+    SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap()
+}
+
+#[test]
+fn finds_nothing_in_consistent_files() {
+    assert_eq!(fixture_metadata("lint_consistent").lint(), Vec::new());
+}
+
+#[test]
+fn finds_bitmap_inconsistencies() {
+    let mut sg_file = fixture_metadata("lint_bitmaps");
+    sg_file.bitmaps[0].start_index = 2;
+    sg_file.bitmaps[0].num_images = 3;
+    sg_file.raw_images[4].bitmap_id = 3;
+    sg_file.max_image_count = 2;
+
+    let findings = sg_file.lint();
+
+    assert_eq!(
+        findings[..4],
+        [
+            LintFinding::TooManyImages { count: 4, max_image_count: 2 },
+            LintFinding::InvalidBitmapId { image: 4, bitmap_id: 3 },
+            LintFinding::BitmapIndexMismatch { bitmap: 0, field: BitmapIndexField::StartIndex, stated: 2, actual: 1 },
+            LintFinding::BitmapIndexMismatch { bitmap: 0, field: BitmapIndexField::EndIndex, stated: 4, actual: 3 },
+        ]
+    );
+}
+
+#[test]
+fn finds_data_inconsistencies() {
+    let mut sg_file = fixture_metadata("lint_data");
+    let data_size = sg_file.file_size_555;
+    sg_file.raw_images[3].invert_offset = 2;
+    sg_file.raw_images[2].offset -= 2;
+    sg_file.bitmaps[0].file_size_555 += 10;
+    sg_file.raw_images[4].offset = data_size - 4;
+
+    let findings = sg_file.lint();
+
+    assert!(findings.contains(&LintFinding::InvalidMirrorSource { image: 3, invert_offset: 2 }));
+    assert!(findings.contains(&LintFinding::OverlappingData { image: 2, other: 1 }));
+    assert!(findings.contains(&LintFinding::DataOutOfBounds {
+        image: 4,
+        end: data_size as u64 - 4 + sg_file.raw_images[4].data_length() as u64,
+        size: data_size as u64
+    }));
+    assert!(findings.contains(&LintFinding::DataSizeMismatch {
+        bitmap: Some(0),
+        field: DataSizeField::FileSize555,
+        stated: data_size + 10,
+        actual: data_size as u64
+    }));
+}
+
+#[test]
+fn checks_external_data_of_bitmaps_with_short_names() {
+    for name in ["", "a", "é", "ab.c"] {
+        let mut sg_file = fixture_metadata("lint_short_names");
+        sg_file.bitmaps[0].external_filename = String::from(name);
+        for raw_image in &mut sg_file.raw_images[1..] {
+            raw_image.flags[0] = 1;
+        }
+
+        let findings = sg_file.lint();
+
+        assert!(!findings.iter().any(|finding| matches!(finding, LintFinding::OverlappingData { .. })), "{:?}", name);
+        assert!(findings.iter().any(|finding| matches!(finding, LintFinding::DataSizeMismatch { bitmap: None, field: DataSizeField::FileSizeExternal, .. })));
+    }
+}
@@ -0,0 +1,386 @@
+mod common;
+
+use common::*;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use sg_image_reader::{
+    AlphaMode,
+    Game,
+    ImageBuilder,
+    ImageBuilderFactory,
+    LoadOptions,
+    MirrorMode,
+    SgBitmapMetadata,
+    SgFileMetadata,
+    SgImageData,
+    SgImageError,
+    SgImageMetadata,
+    TryImageBuilderFactory,
+    VecImageBuilder,
+    VecImageBuilderFactory,
+};
+
+/// Images of every kind, with alpha masks only for versions supporting them.
+fn all_images(version: u32) -> Vec<FixtureImage> {
+    let alpha = |image: FixtureImage| if version >= 0xd6 { with_alpha_mask(image) } else { image };
+    let sprite = sprite_image(12, 7);
+
+    vec![
+        plain_image(9, 4),
+        isometric_image(1, false, 0),
+        isometric_image(2, false, 25),
+        isometric_image(1, true, 10),
+        isometric_image(2, true, 0),
+        sprite_image(300, 2),
+        sprite.clone(),
+        mirrored_image(&sprite, 1),
+        sprite.clone(),
+        mirrored_image(&sprite, 1),
+        alpha(plain_image(5, 6)),
+        alpha(sprite_image(6, 40)),
+        alpha(isometric_image(1, false, 12)),
+    ]
+}
+
+fn assert_pixels(fixture: &Fixture, pixels: &[Vec<u8>]) {
+    assert_eq!(pixels.len(), fixture.expected.len());
+
+    for (index, (pixels, expected)) in pixels.iter().zip(fixture.expected.iter()).enumerate() {
+        assert!(pixels == expected, "pixels of image {} differ", index);
+    }
+}
+
+#[test]
+fn loads_images_of_all_versions() {
+    for version in [0xd3, 0xd5, 0xd6] {
+        let fixture = write_fixture("all_images", version, &all_images(version));
+
+        let (sg_file, pixels) = SgFileMetadata::load_fully(&fixture.sg_path, &VecImageBuilderFactory).unwrap();
+
+        assert_eq!(sg_file.version, version);
+        assert_eq!(sg_file.bitmaps.len(), 1);
+        assert_pixels(&fixture, &pixels);
+    }
+}
+
+#[test]
+fn derives_mirrored_images() {
+    let fixture = write_fixture("derived_mirrors", 0xd6, &all_images(0xd6));
+    let options = LoadOptions {
+        mirror_mode: MirrorMode::Derive,
+        ..LoadOptions::default()
+    };
+
+    let (_, pixels) = SgFileMetadata::load_fully_with_options(&fixture.sg_path, &VecImageBuilderFactory, &options).unwrap();
+
+    assert_pixels(&fixture, &pixels);
+}
+
+#[test]
+fn deduplicates_mirrored_images() {
+    let fixture = write_fixture("deduplicated", 0xd5, &all_images(0xd5));
+
+    let (_, images) = SgFileMetadata::load_fully_deduplicated(&fixture.sg_path, &VecImageBuilderFactory, &LoadOptions::default()).unwrap();
+
+    assert_eq!(images[8], SgImageData::Mirrored(7));
+    assert_eq!(images[10], SgImageData::Mirrored(9));
+    assert_eq!(images[9], SgImageData::Decoded(fixture.expected[9].clone()));
+}
+
+#[test]
+fn premultiplies_alpha_in_all_mirror_modes() {
+    let sprite = sprite_image(7, 5);
+    let alpha_sprite = with_alpha_mask(sprite_image(6, 40));
+    let images = [
+        with_alpha_mask(plain_image(5, 6)),
+        sprite.clone(),
+        mirrored_image(&sprite, 1),
+        alpha_sprite.clone(),
+        mirrored_image(&alpha_sprite, 1),
+        with_alpha_mask(isometric_image(1, false, 12)),
+    ];
+    let fixture = write_fixture("premultiplied", 0xd6, &images);
+
+    let premultiply = |pixel: &[u8]| {
+        let channel = |channel: u8| ((channel as u16 * pixel[3] as u16 + 127) / 255) as u8;
+        [channel(pixel[0]), channel(pixel[1]), channel(pixel[2]), pixel[3]]
+    };
+
+    for mirror_mode in [MirrorMode::Decode, MirrorMode::Derive] {
+        let options = LoadOptions {
+            alpha_mode: AlphaMode::Premultiplied,
+            mirror_mode,
+            ..LoadOptions::default()
+        };
+
+        let (sg_file, pixels) = SgFileMetadata::load_fully_with_options(&fixture.sg_path, &VecImageBuilderFactory, &options).unwrap();
+
+        // The mirrored image shares the alpha mask of its source
+        assert!(sg_file.images[5].alpha_length > 0 && sg_file.images[5].is_mirrored());
+
+        for (index, (image, pixels)) in sg_file.images.iter().zip(pixels.iter()).enumerate() {
+            let expected: Vec<u8> = match image.alpha_length {
+                0 => fixture.expected[index].clone(),
+                _ => fixture.expected[index].chunks_exact(4).flat_map(premultiply).collect(),
+            };
+            assert!(*pixels == expected, "pixels of image {} differ with {:?}", index, mirror_mode);
+        }
+    }
+}
+
+#[test]
+fn decodes_images_with_invalid_mirror_sources() {
+    let sprite = sprite_image(7, 5);
+    // Images 2 and 3 mirror the dummy record and the image after them
+    let images = [sprite.clone(), mirrored_image(&sprite, 2), mirrored_image(&sprite, -1), sprite.clone()];
+    let fixture = write_fixture("invalid_mirrors", 0xd5, &images);
+
+    let (_, decoded) = SgFileMetadata::load_fully(&fixture.sg_path, &VecImageBuilderFactory).unwrap();
+    let (_, images) = SgFileMetadata::load_fully_deduplicated(&fixture.sg_path, &VecImageBuilderFactory, &LoadOptions::default()).unwrap();
+
+    assert_eq!(images.len(), decoded.len());
+    for (image, decoded) in images.into_iter().zip(decoded) {
+        assert_eq!(image, SgImageData::Decoded(decoded));
+    }
+}
+
+#[test]
+fn loads_images_from_stream() {
+    let fixture = write_fixture("streamed", 0xd6, &all_images(0xd6));
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+
+    // Chained readers do not implement Seek
+    let mut reader = data.as_slice().chain(&[][..]);
+    let images = sg_file.load_images_from_stream("streamed.555", &mut reader, &VecImageBuilderFactory, &LoadOptions::default()).unwrap();
+
+    let (indexes, pixels): (Vec<usize>, Vec<Vec<u8>>) = images.into_iter().unzip();
+    assert_eq!(indexes, (0..fixture.expected.len()).collect::<Vec<_>>());
+    assert_pixels(&fixture, &pixels);
+
+    let mut reader = &data[..data.len() - 1];
+    let err = sg_file.load_images_from_stream("streamed.555", &mut reader, &VecImageBuilderFactory, &LoadOptions::default()).unwrap_err();
+    assert!(matches!(err, SgImageError::UnexpectedEof), "{:?}", err);
+}
+
+/// Factory building just the metadata the builders were created for.
+struct MetadataFactory;
+
+struct MetadataBuilder(u16, bool, String);
+
+impl ImageBuilderFactory<(u16, bool, String)> for MetadataFactory {
+    type Builder = MetadataBuilder;
+
+    fn new_builder(&self, _width: u16, _height: u16) -> Self::Builder {
+        unreachable!("builders are created for images")
+    }
+
+    fn new_builder_for_image(&self, image: &SgImageMetadata, bitmap: Option<&SgBitmapMetadata>) -> Self::Builder {
+        MetadataBuilder(image.image_type, image.is_mirrored(), bitmap.unwrap().comment.clone())
+    }
+}
+
+impl ImageBuilder<(u16, bool, String)> for MetadataBuilder {
+    fn set_pixel_by_pos(&mut self, _position: usize, _data: [u8; 4]) {}
+
+    fn set_alpha(&mut self, _position: usize, _alpha: u8) {}
+
+    fn flip_horizontal(&mut self) {}
+
+    fn build(self) -> (u16, bool, String) {
+        (self.0, self.1, self.2)
+    }
+}
+
+#[test]
+fn creates_builders_for_image_metadata() {
+    let fixture = write_fixture("builder_metadata", 0xd5, &all_images(0xd5));
+
+    for mirror_mode in [MirrorMode::Decode, MirrorMode::Derive] {
+        let options = LoadOptions {
+            mirror_mode,
+            ..LoadOptions::default()
+        };
+
+        let (sg_file, images) = SgFileMetadata::load_fully_with_options(&fixture.sg_path, &MetadataFactory, &options).unwrap();
+
+        for (image, (image_type, is_mirrored, comment)) in sg_file.images.iter().zip(images) {
+            assert_eq!(image_type, image.image_type);
+            assert_eq!(is_mirrored, image.is_mirrored());
+            assert_eq!(comment, "Fixture");
+        }
+    }
+
+    // Loading single images, with the bitmap given by the caller
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+    let mut reader = BufReader::new(File::open(sg_file.get_555_file_path(0, false)).unwrap());
+    let bitmap = sg_file.bitmaps.first();
+
+    for image in &sg_file.images {
+        let expected = (image.image_type, image.is_mirrored(), String::from("Fixture"));
+        assert_eq!(image.load_image_with_bitmap(&mut reader, bitmap, &MetadataFactory, &LoadOptions::default()).unwrap(), expected);
+        assert_eq!(image.load_image_from_bytes_with_bitmap(&data, bitmap, &MetadataFactory, &LoadOptions::default()).unwrap(), expected);
+    }
+}
+
+/// Factory refusing to create builders for images larger than the given number of pixels.
+struct LimitedFactory(usize);
+
+#[derive(Debug)]
+struct TooLarge(u32);
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "image {} is too large", self.0)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+impl TryImageBuilderFactory<Vec<u8>> for LimitedFactory {
+    type Builder = VecImageBuilder;
+    type Error = TooLarge;
+
+    fn try_new_builder_for_image(&self, image: &SgImageMetadata, _bitmap: Option<&SgBitmapMetadata>) -> Result<Self::Builder, Self::Error> {
+        if image.width as usize * image.height as usize > self.0 {
+            return Err(TooLarge(image.id));
+        }
+
+        Ok(VecImageBuilderFactory.new_builder(image.width, image.height))
+    }
+}
+
+#[test]
+fn returns_builder_errors() {
+    let fixture = write_fixture("builder_errors", 0xd5, &all_images(0xd5));
+
+    let (_, pixels) = SgFileMetadata::load_fully(&fixture.sg_path, &LimitedFactory(usize::MAX)).unwrap();
+    assert_pixels(&fixture, &pixels);
+
+    let err = SgFileMetadata::load_fully(&fixture.sg_path, &LimitedFactory(500)).unwrap_err();
+    assert_eq!(err.to_string(), "image builder error encountered: image 2 is too large");
+
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let mut reader = BufReader::new(File::open(sg_file.get_555_file_path(0, false)).unwrap());
+    let err = sg_file.images[6].load_image(&mut reader, &LimitedFactory(500)).unwrap_err();
+    assert!(matches!(err, SgImageError::BuilderError(err) if err.is::<TooLarge>()));
+}
+
+#[test]
+fn rejects_unsupported_versions() {
+    let fixture = write_fixture("unsupported", 0xd4, &[plain_image(2, 2)]);
+
+    let err = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap_err();
+
+    assert!(matches!(err, SgImageError::UnsupportedVersion(0xd4)));
+    assert_eq!(err.to_string(), "unsupported sg file version: 0xd4");
+}
+
+#[test]
+fn detects_games() {
+    let cases = [
+        ("c3_north", 0xd3, Game::Caesar3),
+        ("enemy", 0xd3, Game::Caesar3),
+        ("Pharaoh_General", 0xd5, Game::Pharaoh),
+        ("Poseidon_Terrain", 0xd5, Game::Zeus),
+        ("Emperor", 0xd6, Game::Emperor),
+        ("unnamed", 0xd6, Game::Unknown),
+    ];
+
+    for (name, version, game) in cases {
+        let fixture = write_fixture(name, version, &[plain_image(2, 2)]);
+
+        let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+        assert_eq!(sg_file.format_version().map(|format_version| format_version.version()), Some(version));
+        assert_eq!(sg_file.game(), game, "{}", name);
+    }
+
+    // Header sizes of Caesar 3 SG2 files take precedence over names, which are used for other sizes
+    let fixture = write_fixture("pharaoh_sg2", 0xd3, &[plain_image(2, 2)]);
+    let mut sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    assert_eq!(sg_file.game(), Game::Caesar3);
+
+    sg_file.file_size = 522680;
+    assert_eq!(sg_file.game(), Game::Caesar3);
+
+    sg_file.file_size = 1000;
+    assert_eq!(sg_file.game(), Game::Pharaoh);
+
+    sg_file.filename = String::from("unnamed.sg2");
+    sg_file.bitmaps[0].external_filename = String::from("unnamed.bmp");
+    assert_eq!(sg_file.game(), Game::Unknown);
+}
+
+#[test]
+fn resolves_mirrored_image_records() {
+    let fixture = write_fixture("mirrored_records", 0xd5, &all_images(0xd5));
+
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+    assert_eq!(sg_file.images[8].mirror_source(), Some(7));
+    assert_eq!(sg_file.images[8].length, sg_file.images[7].length);
+    assert_eq!(sg_file.raw_images[8].length, 0);
+}
+
+#[test]
+fn rejects_inconsistent_image_data() {
+    let fixture = write_fixture("inconsistent_data", 0xd6, &all_images(0xd6));
+
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+    let load = |image: &SgImageMetadata| image.load_image_from_bytes(&data, &VecImageBuilderFactory, &LoadOptions::default());
+
+    // Runs crossing the stated length of the pixel data or the alpha mask
+    let mut sprite = sg_file.images[7].clone();
+    sprite.length -= 1;
+    assert!(matches!(load(&sprite), Err(SgImageError::ImageDataLengthMismatch)));
+
+    let mut alpha_sprite = sg_file.images[12].clone();
+    alpha_sprite.alpha_length -= 1;
+    assert!(matches!(load(&alpha_sprite), Err(SgImageError::ImageDataLengthMismatch)));
+
+    // Runs past the last pixel of the image
+    let mut sprite = sg_file.images[7].clone();
+    sprite.height = 1;
+    assert!(matches!(load(&sprite), Err(SgImageError::ImageDataLengthMismatch)));
+
+    // Isometric image with a base larger than the whole pixel data, or taller than the image
+    let mut isometric = sg_file.images[2].clone();
+    isometric.length = isometric.uncompressed_length - 1;
+    assert!(matches!(load(&isometric), Err(SgImageError::ImageDataLengthMismatch)));
+
+    let mut isometric = sg_file.images[2].clone();
+    isometric.height = 10;
+    assert!(matches!(load(&isometric), Err(SgImageError::ImageDataLengthMismatch)));
+}
+
+#[test]
+fn names_data_files_of_short_bitmap_names() {
+    let fixture = write_fixture("short_names", 0xd5, &[plain_image(2, 2)]);
+    let mut sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+    assert_eq!(sg_file.get_555_file_name(0, false), "short_names.555");
+
+    for (name, data_file_name) in [("", ".555"), ("a", "a.555"), ("ab.c", "ab.555"), ("Zeus.bmp", "Zeus.555"), ("é.bmp", "é.555")] {
+        sg_file.bitmaps[0].external_filename = String::from(name);
+        assert_eq!(sg_file.get_555_file_name(0, true), data_file_name);
+    }
+
+    assert_eq!(sg_file.get_555_file_name(7, true), ".555");
+}
+
+#[test]
+fn reports_truncated_data_the_same_for_slices_and_readers() {
+    let fixture = write_fixture("truncated", 0xd5, &[plain_image(4, 4)]);
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let mut data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+    data.truncate(data.len() - 3);
+
+    let err = sg_file.images[1].load_image_from_bytes(&data, &VecImageBuilderFactory, &LoadOptions::default()).unwrap_err();
+    assert!(matches!(err, SgImageError::UnexpectedEof));
+
+    let err = sg_file.images[1].load_image(&mut BufReader::new(std::io::Cursor::new(data)), &VecImageBuilderFactory).unwrap_err();
+    assert!(matches!(err, SgImageError::UnexpectedEof));
+}
@@ -0,0 +1,156 @@
+use sg_image_reader::{
+    AlphaMode,
+    ImageBuilder,
+    ImageBuilderFactory,
+    LoadOptions,
+    SgImageMetadata,
+    TransparencyMode,
+    VecImageBuilderFactory,
+    DEFAULT_TRANSPARENT_COLOUR,
+};
+use std::io::{BufReader, Cursor};
+
+const FILL: [u8; 4] = [0x55; 4];
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+const MAGENTA: [u8; 4] = [240, 0, 248, 255];
+
+/// Factory of builders with all the pixels initialised to [FILL], to tell skipped pixels apart from written ones.
+struct FilledFactory;
+
+struct FilledBuilder(Vec<u8>);
+
+impl ImageBuilderFactory<Vec<u8>> for FilledFactory {
+    type Builder = FilledBuilder;
+
+    fn new_builder(&self, width: u16, height: u16) -> Self::Builder {
+        FilledBuilder(FILL.repeat(width as usize * height as usize))
+    }
+}
+
+impl ImageBuilder<Vec<u8>> for FilledBuilder {
+    fn set_pixel_by_pos(&mut self, position: usize, data: [u8; 4]) {
+        self.0[position * 4..(position + 1) * 4].copy_from_slice(&data);
+    }
+
+    fn set_alpha(&mut self, position: usize, alpha: u8) {
+        self.0[position * 4 + 3] = alpha;
+    }
+
+    fn flip_horizontal(&mut self) {
+        unreachable!("no mirrored images are loaded")
+    }
+
+    fn build(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Get the 555 colour and the RGBA pixel it decodes to for the given seed.
+fn colour(seed: usize) -> (u16, [u8; 4]) {
+    let (r, g, b) = ((seed * 7 % 32) as u16, (seed * 13 % 32) as u16, (seed * 3 % 32) as u16);
+    ((r << 10) | (g << 5) | b, [(r << 3) as u8, (g << 3) as u8, (b << 3) as u8, 255])
+}
+
+/// Plain 5x4 image record along with its pixel data, followed by the given alpha mask.
+fn plain_image(colours: &[u16], alpha_mask: &[u8]) -> (SgImageMetadata, Vec<u8>) {
+    let mut data: Vec<u8> = colours.iter().flat_map(|colour| colour.to_le_bytes()).collect();
+    let length = data.len() as u32;
+    data.extend_from_slice(alpha_mask);
+
+    let image = SgImageMetadata {
+        width: 5,
+        height: 4,
+        length,
+        alpha_offset: if alpha_mask.is_empty() { 0 } else { length },
+        alpha_length: alpha_mask.len() as u32,
+        ..SgImageMetadata::default()
+    };
+
+    (image, data)
+}
+
+/// Load the plain image, with every fifth pixel of the default transparent colour, with the given options.
+fn load_plain_image(options: &LoadOptions) -> Vec<u8> {
+    let colours: Vec<u16> = (0..20).map(|position| if position % 5 == 4 { DEFAULT_TRANSPARENT_COLOUR } else { colour(position).0 }).collect();
+    let (image, data) = plain_image(&colours, &[]);
+
+    image.load_image_with_options(&mut BufReader::new(Cursor::new(data)), &FilledFactory, options).unwrap()
+}
+
+/// Expected pixels of the plain image, given the pixels to use for the default transparent colour and the colour of the given seed.
+fn expected_plain_image(transparent: [u8; 4], seed: usize, keyed: [u8; 4]) -> Vec<u8> {
+    (0..20)
+        .flat_map(|position| match position {
+            _ if position % 5 == 4 => transparent,
+            _ if position == seed => keyed,
+            _ => colour(position).1,
+        })
+        .collect()
+}
+
+#[test]
+fn handles_transparent_colours_by_mode() {
+    let cases = [(TransparencyMode::Skip, FILL), (TransparencyMode::TransparentBlack, TRANSPARENT), (TransparencyMode::Opaque, MAGENTA)];
+
+    for (transparency_mode, transparent) in cases {
+        let options = LoadOptions {
+            transparency_mode,
+            ..LoadOptions::default()
+        };
+
+        assert_eq!(load_plain_image(&options), expected_plain_image(transparent, 0, colour(0).1), "{:?}", transparency_mode);
+    }
+}
+
+#[test]
+fn handles_custom_transparent_colours() {
+    let (keyed_colour, keyed_pixel) = colour(7);
+
+    let options = LoadOptions {
+        transparent_colours: vec![keyed_colour],
+        ..LoadOptions::default()
+    };
+    assert_eq!(load_plain_image(&options), expected_plain_image(MAGENTA, 7, FILL));
+
+    let options = LoadOptions {
+        transparent_colours: vec![keyed_colour, DEFAULT_TRANSPARENT_COLOUR],
+        transparency_mode: TransparencyMode::TransparentBlack,
+        ..LoadOptions::default()
+    };
+    assert_eq!(load_plain_image(&options), expected_plain_image(TRANSPARENT, 7, TRANSPARENT));
+
+    let options = LoadOptions {
+        transparent_colours: Vec::new(),
+        ..LoadOptions::default()
+    };
+    assert_eq!(load_plain_image(&options), expected_plain_image(MAGENTA, 7, keyed_pixel));
+}
+
+#[test]
+fn premultiplies_alpha_of_masked_images() {
+    let colours: Vec<u16> = (0..20).map(|position| colour(position).0).collect();
+    // The first pixel keeps its opaque alpha, the next ten get alpha from the mask
+    let alpha: Vec<u8> = (0..10).map(|position| position * 3 + 2).collect();
+    let mut alpha_mask = vec![255, 1, 10];
+    alpha_mask.extend_from_slice(&alpha);
+    let (image, data) = plain_image(&colours, &alpha_mask);
+
+    for (alpha_mode, premultiplied) in [(AlphaMode::Straight, false), (AlphaMode::Premultiplied, true)] {
+        let options = LoadOptions {
+            alpha_mode,
+            ..LoadOptions::default()
+        };
+
+        let pixels = image.load_image_with_options(&mut BufReader::new(Cursor::new(data.clone())), &VecImageBuilderFactory, &options).unwrap();
+
+        let expected: Vec<u8> = (0..20)
+            .flat_map(|position| {
+                let pixel = colour(position).1;
+                let alpha = if (1..11).contains(&position) { alpha[position - 1] << 3 } else { 255 };
+                let channel = |channel: u8| if premultiplied { ((channel as u16 * alpha as u16 + 127) / 255) as u8 } else { channel };
+                [channel(pixel[0]), channel(pixel[1]), channel(pixel[2]), alpha]
+            })
+            .collect();
+        assert_eq!(pixels, expected, "{:?}", alpha_mode);
+    }
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::*;
+use sg_image_reader::{RegionImageBuilderFactory, RegionImageError, SgFileMetadata, SgImageError, SgImageMetadata};
+
+const FILL: u8 = 0xaa;
+
+/// Load the image into a buffer of the given size at the given position, returning the whole buffer.
+fn load_into_region(sg_file: &SgFileMetadata, image: &SgImageMetadata, buffer_size: (usize, usize), x: usize, y: usize) -> Vec<u8> {
+    let data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+    let stride = buffer_size.0 * 4;
+    let mut buffer = vec![FILL; stride * buffer_size.1];
+
+    image.load_image_from_bytes(&data, &RegionImageBuilderFactory::new(&mut buffer, stride, x, y), &Default::default()).unwrap();
+
+    buffer
+}
+
+/// Check that the region holds the expected pixels and that the rest of the buffer was left untouched.
+fn assert_region(buffer: &[u8], stride: usize, (x, y): (usize, usize), (width, height): (usize, usize), expected: &[u8]) {
+    for (row, bytes) in buffer.chunks(stride).enumerate() {
+        let in_rows = row >= y && row < y + height;
+
+        for (column, pixel) in bytes.chunks(4).enumerate() {
+            if in_rows && column >= x && column < x + width {
+                let i = ((row - y) * width + column - x) * 4;
+                assert_eq!(pixel, &expected[i..(i + 4)], "pixel {}x{}", column, row);
+            } else {
+                assert_eq!(pixel, [FILL; 4], "pixel {}x{} outside of the region", column, row);
+            }
+        }
+    }
+}
+
+#[test]
+fn writes_images_into_strided_regions() {
+    let sprite = sprite_image(7, 5);
+    let images = [plain_image(4, 3), sprite.clone(), mirrored_image(&sprite, 1), with_alpha_mask(sprite_image(6, 4))];
+    let fixture = write_fixture("region", 0xd6, &images);
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+    for (index, (x, y)) in [(1, (0, 0)), (1, (6, 4)), (2, (3, 1)), (3, (3, 1)), (4, (0, 6))] {
+        let image = &sg_file.images[index];
+        let size = (image.width as usize, image.height as usize);
+
+        let buffer = load_into_region(&sg_file, image, (10, 10), x, y);
+
+        assert_region(&buffer, 40, (x, y), size, &fixture.expected[index]);
+    }
+}
+
+#[test]
+fn rejects_regions_not_fitting_the_buffer() {
+    let fixture = write_fixture("region_errors", 0xd5, &[plain_image(4, 3)]);
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let data = std::fs::read(sg_file.get_555_file_path(0, false)).unwrap();
+    let image = &sg_file.images[1];
+    let mut buffer = vec![FILL; 40 * 10];
+
+    let load = |factory: &RegionImageBuilderFactory| match image.load_image_from_bytes(&data, factory, &Default::default()) {
+        Err(SgImageError::BuilderError(err)) => err.downcast_ref::<RegionImageError>().copied(),
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    };
+
+    assert_eq!(load(&RegionImageBuilderFactory::new(&mut buffer, 40, 7, 0)), Some(RegionImageError::RowTooShort));
+    assert_eq!(load(&RegionImageBuilderFactory::new(&mut buffer, 40, 0, 8)), Some(RegionImageError::BufferTooSmall));
+    assert_eq!(load(&RegionImageBuilderFactory::new(&mut buffer, 40, usize::MAX, 0)), Some(RegionImageError::RowTooShort));
+    assert_eq!(load(&RegionImageBuilderFactory::new(&mut buffer, 40, 0, usize::MAX)), Some(RegionImageError::BufferTooSmall));
+    assert_eq!(load(&RegionImageBuilderFactory::new(&mut buffer, usize::MAX, 0, 0)), Some(RegionImageError::BufferTooSmall));
+    assert!(buffer.iter().all(|&byte| byte == FILL));
+
+    let factory = RegionImageBuilderFactory::new(&mut buffer, 40, 6, 7);
+    image.load_image_from_bytes(&data, &factory, &Default::default()).unwrap();
+    assert_eq!(load(&factory), Some(RegionImageError::BufferAlreadyUsed));
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "image")]
+
+mod common;
+
+use common::*;
+use sg_image_reader::{SgFileMetadata, SgImageError};
+
+#[test]
+fn saves_images_as_png() {
+    let fixture = write_fixture("png", 0xd5, &[plain_image(4, 3), sprite_image(5, 6)]);
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+    let png_path = fixture.sg_path.with_file_name("sprite.png");
+
+    sg_file.save_image_png(2, &png_path).unwrap();
+
+    let png = image::open(&png_path).unwrap().to_rgba8();
+    assert_eq!(png.dimensions(), (5, 6));
+    assert_eq!(png.into_raw(), fixture.expected[2]);
+
+    let err = sg_file.save_image_png(3, &png_path).unwrap_err();
+    assert!(matches!(err, SgImageError::InvalidImageIndex(3)));
+}
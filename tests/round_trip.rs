@@ -0,0 +1,405 @@
+//! Checks that metadata written back with `SgFileMetadata::to_bytes` matches the loaded file byte for byte,
+//! and that pixels encoded with `SgImageMetadata::encode_image` decode to the same pixels.
+//!
+//! Real files can be checked by pointing `SG_ROUND_TRIP_DIR` at a folder containing them.
+mod common;
+
+use std::fs;
+use std::io::{BufReader, Cursor};
+
+use proptest::prelude::*;
+use sg_image_reader::{LoadOptions, SgBitmapMetadata, SgFileMetadata, SgImageMetadata, TextEncoding, VecImageBuilderFactory};
+
+const HEADER_FIELDS: [&str; 10] = [
+    "file_size",
+    "version",
+    "unknown",
+    "max_image_count",
+    "image_count",
+    "bitmap_count",
+    "bitmap_records_without_system",
+    "total_file_size",
+    "file_size_555",
+    "file_size_external",
+];
+
+const BITMAP_FIELDS: [(&str, usize); 18] = [
+    ("external_filename", 65),
+    ("comment", 51),
+    ("width", 4),
+    ("height", 4),
+    ("num_images", 4),
+    ("start_index", 4),
+    ("end_index", 4),
+    ("image_id", 4),
+    ("unknown_a", 4),
+    ("unknown_b", 4),
+    ("unknown_c", 4),
+    ("unknown_d", 4),
+    ("image_width", 4),
+    ("image_height", 4),
+    ("file_size_555", 4),
+    ("total_file_size", 4),
+    ("file_size_external", 4),
+    ("unknown_e", 24),
+];
+
+const IMAGE_FIELDS: [(&str, usize); 23] = [
+    ("offset", 4),
+    ("length", 4),
+    ("uncompressed_length", 4),
+    ("zeroes", 4),
+    ("invert_offset", 4),
+    ("width", 2),
+    ("height", 2),
+    ("unknown_a", 6),
+    ("anim_sprites", 2),
+    ("unknown_b", 2),
+    ("x_offset", 2),
+    ("y_offset", 2),
+    ("unknown_c", 10),
+    ("is_reversible", 1),
+    ("unknown_d", 1),
+    ("image_type", 2),
+    ("flags", 4),
+    ("bitmap_id", 1),
+    ("unknown_e", 1),
+    ("anim_speed_id", 1),
+    ("unknown_f", 5),
+    ("alpha_offset", 4),
+    ("alpha_length", 4),
+];
+
+/// Name the field of the sg file stored at the given position.
+fn field_name(sg_file: &SgFileMetadata, position: usize) -> String {
+    if position < 40 {
+        return format!("header.{}", HEADER_FIELDS[position / 4]);
+    }
+    if position < 80 {
+        return format!("header.unknown_header[{}]", (position - 40) / 4);
+    }
+    if position < 680 {
+        return format!("header.group_image_ids[{}]", (position - 80) / 2);
+    }
+
+    let max_bitmap_records = if sg_file.version == 0xd3 { 100 } else { 200 };
+    let bitmaps_end = 680 + 200 * max_bitmap_records;
+
+    if position < bitmaps_end {
+        let record = (position - 680) / 200;
+        if record >= sg_file.bitmaps.len() {
+            return format!("unused bitmap record {}", record);
+        }
+        return format!("bitmaps[{}].{}", record, record_field(&BITMAP_FIELDS, (position - 680) % 200));
+    }
+
+    let image_record_size = if sg_file.version >= 0xd6 { 72 } else { 64 };
+    let record = (position - bitmaps_end) / image_record_size;
+
+    if record < sg_file.raw_images.len() {
+        format!("raw_images[{}].{}", record, record_field(&IMAGE_FIELDS, (position - bitmaps_end) % image_record_size))
+    } else {
+        String::from("data after image records")
+    }
+}
+
+fn record_field(fields: &[(&'static str, usize)], mut position: usize) -> &'static str {
+    for (name, size) in fields {
+        if position < *size {
+            return name;
+        }
+        position -= size;
+    }
+    "unknown field"
+}
+
+/// Load the file contents, write them back and compare, panicking with the name of the first field that differs.
+fn assert_round_trip(name: &str, original: &[u8]) {
+    let mut reader = BufReader::new(Cursor::new(original));
+    let sg_file = SgFileMetadata::load_metadata_from_reader(&mut reader, String::new(), String::from(name))
+        .unwrap_or_else(|err| panic!("{}: failed to load: {}", name, err));
+
+    let written = sg_file.to_bytes();
+
+    let mismatch = original.iter().zip(written.iter()).position(|(a, b)| a != b);
+
+    if let Some(position) = mismatch {
+        panic!(
+            "{}: byte {} differs in {}: expected {:#04x}, written {:#04x}",
+            name,
+            position,
+            field_name(&sg_file, position),
+            original[position],
+            written[position]
+        );
+    }
+
+    if original.len() != written.len() {
+        let position = original.len().min(written.len());
+        panic!("{}: {} bytes read, {} bytes written, starting to differ in {}", name, original.len(), written.len(), field_name(&sg_file, position));
+    }
+}
+
+/// Build the metadata of a sg file with the given images in a single bitmap.
+fn synthetic_file(version: u32, mut images: Vec<SgImageMetadata>) -> SgFileMetadata {
+    for (i, image) in images.iter_mut().enumerate() {
+        image.id = i as u32 + 1;
+        image.bitmap_id = 0;
+        if version < 0xd6 {
+            image.alpha_offset = 0;
+            image.alpha_length = 0;
+        }
+    }
+
+    let mut raw_images = vec![SgImageMetadata::default()];
+    raw_images.append(&mut images);
+
+    let bitmap = SgBitmapMetadata {
+        external_filename: String::from("synthetic.bmp"),
+        comment: String::from("Synthetic"),
+        num_images: raw_images.len() as u32 - 1,
+        start_index: 1,
+        end_index: raw_images.len() as u32 - 1,
+        ..SgBitmapMetadata::default()
+    };
+
+    let mut sg_file = SgFileMetadata {
+        filename: String::from("synthetic.sg3"),
+        version,
+        max_image_count: raw_images.len() as u32,
+        bitmap_records_without_system: 1,
+        group_image_ids: vec![0; 300],
+        bitmaps: vec![bitmap],
+        images: raw_images.clone(),
+        raw_images,
+        ..SgFileMetadata::default()
+    };
+
+    sg_file.file_size = if version == 0xd3 { 74480 } else { sg_file.to_bytes().len() as u32 };
+    sg_file
+}
+
+/// Image records with any field values, apart from not being mirrored.
+fn image_record() -> impl Strategy<Value = SgImageMetadata> {
+    let data = (any::<u32>(), any::<u32>(), any::<u32>(), any::<[u8; 4]>(), any::<u16>(), any::<u16>(), any::<[u16; 3]>(), any::<u16>(), any::<u16>());
+    let placement = (any::<u16>(), any::<u16>(), any::<[u8; 10]>(), any::<u8>(), any::<u8>(), any::<u16>(), any::<[u8; 4]>());
+    let rest = (any::<u8>(), any::<u8>(), any::<[u8; 5]>(), any::<u32>(), any::<u32>());
+
+    (data, placement, rest).prop_map(|(data, placement, rest)| {
+        let (offset, length, uncompressed_length, zeroes, width, height, unknown_a, anim_sprites, unknown_b) = data;
+        let (x_offset, y_offset, unknown_c, is_reversible, unknown_d, image_type, flags) = placement;
+        let (unknown_e, anim_speed_id, unknown_f, alpha_offset, alpha_length) = rest;
+
+        SgImageMetadata {
+            offset,
+            length,
+            uncompressed_length,
+            zeroes,
+            width,
+            height,
+            unknown_a,
+            anim_sprites,
+            unknown_b,
+            x_offset,
+            y_offset,
+            unknown_c,
+            is_reversible,
+            unknown_d,
+            image_type,
+            flags,
+            unknown_e,
+            anim_speed_id,
+            unknown_f,
+            alpha_offset,
+            alpha_length,
+            ..SgImageMetadata::default()
+        }
+    })
+}
+
+/// Image type along with dimensions valid for it (and isometric size for isometric images).
+fn image_shape() -> impl Strategy<Value = (u16, u16, u16, u8)> {
+    let plain = (prop::sample::select(vec![0_u16, 1, 10, 12, 13, 256, 257, 276]), 1_u16..40, 1_u16..20).prop_map(|(image_type, width, height)| (image_type, width, height, 0));
+    let isometric = (1_u16..4, prop::bool::ANY, 0_u16..30).prop_map(|(size, large, extra_height)| {
+        let tile_width = if large { 78 } else { 58 };
+        let width = size * (tile_width + 2) - 2;
+        (30, width, (width + 2) / 2 + extra_height, size as u8)
+    });
+
+    prop_oneof![plain, isometric]
+}
+
+fn image_with_pixels() -> impl Strategy<Value = (SgImageMetadata, Vec<u8>)> {
+    image_shape().prop_flat_map(|(image_type, width, height, isometric_size)| {
+        let pixel = (any::<[u8; 3]>(), prop::sample::select(vec![0_u8, 255, 255, 255, 1, 100, 254]));
+        prop::collection::vec(pixel, width as usize * height as usize).prop_map(move |pixels| {
+            let mut image = SgImageMetadata {
+                image_type,
+                width,
+                height,
+                ..SgImageMetadata::default()
+            };
+            image.flags[3] = isometric_size;
+
+            let pixels = pixels
+                .into_iter()
+                .flat_map(|([r, g, b], a)| {
+                    // Avoid the colour marking transparent pixels
+                    let g = if r >= 0xf8 && g < 0x08 && b >= 0xf8 { 0x08 } else { g };
+                    [r, g, b, a]
+                })
+                .collect();
+
+            (image, pixels)
+        })
+    })
+}
+
+/// Pixels as they are expected to decode, with the precision of the 555 format and alpha masks.
+fn expected_pixels(pixels: &[u8], include_alpha: bool) -> Vec<u8> {
+    pixels
+        .chunks_exact(4)
+        .flat_map(|pixel| match pixel[3] {
+            0 => [0, 0, 0, 0],
+            255 => [pixel[0] & 0xf8, pixel[1] & 0xf8, pixel[2] & 0xf8, 255],
+            alpha if include_alpha => [pixel[0] & 0xf8, pixel[1] & 0xf8, pixel[2] & 0xf8, (alpha >> 3) << 3],
+            _ => [pixel[0] & 0xf8, pixel[1] & 0xf8, pixel[2] & 0xf8, 255],
+        })
+        .collect()
+}
+
+#[test]
+fn synthetic_files_round_trip() {
+    for version in [0xd3, 0xd5, 0xd6] {
+        let images = vec![
+            SgImageMetadata {
+                width: 10,
+                height: 5,
+                length: 100,
+                uncompressed_length: 100,
+                ..SgImageMetadata::default()
+            },
+            SgImageMetadata {
+                image_type: 256,
+                width: 3,
+                height: 4,
+                offset: 100,
+                length: 30,
+                alpha_offset: 130,
+                alpha_length: 4,
+                ..SgImageMetadata::default()
+            },
+        ];
+
+        let sg_file = synthetic_file(version, images);
+        assert_round_trip(&format!("synthetic {:#x}", version), &sg_file.to_bytes());
+    }
+}
+
+#[test]
+fn fixture_files_round_trip() {
+    for version in [0xd3, 0xd5, 0xd6] {
+        let sprite = common::sprite_image(5, 4);
+        let images = [common::plain_image(3, 2), common::isometric_image(1, true, 4), sprite.clone(), common::mirrored_image(&sprite, 1)];
+        let fixture = common::write_fixture("round_trip", version, &images);
+
+        assert_round_trip(&fixture.sg_path.display().to_string(), &fs::read(&fixture.sg_path).unwrap());
+    }
+}
+
+#[test]
+fn mirrored_image_records_round_trip() {
+    let source = SgImageMetadata {
+        image_type: 256,
+        width: 3,
+        height: 4,
+        length: 30,
+        ..SgImageMetadata::default()
+    };
+    let mirrored = SgImageMetadata {
+        invert_offset: -1,
+        ..SgImageMetadata::default()
+    };
+
+    let sg_file = synthetic_file(0xd5, vec![source, mirrored]);
+    assert_round_trip("mirrored", &sg_file.to_bytes());
+}
+
+#[test]
+fn legacy_strings_round_trip() {
+    let mut sg_file = synthetic_file(0xd5, Vec::new());
+    let mut bytes = sg_file.to_bytes();
+
+    // Windows-1252 name and comment, with garbage left after the terminating NUL
+    let name = b"Caf\xe9_\x80.bmp\0\xff\xfe garbage";
+    let comment = b"\x93Quoted\x94\0\xc3";
+    bytes[680..680 + name.len()].copy_from_slice(name);
+    bytes[680 + 65..680 + 65 + comment.len()].copy_from_slice(comment);
+
+    assert_round_trip("legacy_strings", &bytes);
+
+    sg_file = SgFileMetadata::load_metadata_from_reader(&mut BufReader::new(Cursor::new(&bytes)), String::new(), String::from("legacy.sg3")).unwrap();
+    assert_eq!(sg_file.bitmaps[0].external_filename, "Café_€.bmp");
+    assert_eq!(sg_file.bitmaps[0].comment, "\u{201c}Quoted\u{201d}");
+
+    sg_file.decode_strings(TextEncoding::Utf8Lossy);
+    assert_eq!(sg_file.bitmaps[0].external_filename, "Caf\u{fffd}_\u{fffd}.bmp");
+    assert_eq!(sg_file.to_bytes(), bytes);
+
+    // Edited strings are written instead of the raw bytes they were decoded from
+    sg_file.bitmaps[0].external_filename = String::from("Renamed.bmp");
+    sg_file.bitmaps[0].comment = String::from("Édité");
+    let edited = sg_file.to_bytes();
+    assert_eq!(&edited[680..692], b"Renamed.bmp\0");
+    assert_eq!(&edited[680 + 65..680 + 71], b"\xc9dit\xe9\0");
+
+    let reloaded = SgFileMetadata::load_metadata_from_reader(&mut BufReader::new(Cursor::new(&edited)), String::new(), String::from("legacy.sg3")).unwrap();
+    assert_eq!(reloaded.bitmaps[0].external_filename, "Renamed.bmp");
+    assert_eq!(reloaded.bitmaps[0].comment, "Édité");
+
+    // Strings without raw bytes are encoded as Windows-1252
+    sg_file.bitmaps[0].external_filename = String::from("Café_€.bmp");
+    sg_file.bitmaps[0].external_filename_bytes.clear();
+    assert_eq!(&sg_file.to_bytes()[680..691], b"Caf\xe9_\x80.bmp\0");
+}
+
+#[test]
+fn real_files_round_trip() {
+    let folder = match std::env::var("SG_ROUND_TRIP_DIR") {
+        Ok(folder) => folder,
+        Err(_) => return,
+    };
+
+    for entry in fs::read_dir(folder).unwrap().flatten() {
+        let path = entry.path();
+        let is_sg_file = path.extension().is_some_and(|extension| extension == "sg2" || extension == "sg3");
+
+        if is_sg_file {
+            assert_round_trip(&path.display().to_string(), &fs::read(&path).unwrap());
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn image_records_round_trip(version in prop::sample::select(vec![0xd3_u32, 0xd5, 0xd6]), images in prop::collection::vec(image_record(), 1..5)) {
+        let sg_file = synthetic_file(version, images);
+        assert_round_trip(&format!("records {:#x}", version), &sg_file.to_bytes());
+    }
+
+    #[test]
+    fn pixels_round_trip((mut image, pixels) in image_with_pixels(), include_alpha in prop::bool::ANY) {
+        let data = image.encode_image(&pixels, include_alpha).unwrap();
+        prop_assert_eq!(data.len(), image.length as usize + image.alpha_length as usize);
+
+        let decoded = image.load_image_from_bytes(&data, &VecImageBuilderFactory, &LoadOptions::default()).unwrap();
+        prop_assert_eq!(decoded, expected_pixels(&pixels, include_alpha));
+
+        // The encoded image written to and loaded from a file is the same image
+        let sg_file = synthetic_file(0xd6, vec![image.clone()]);
+        let loaded = SgFileMetadata::load_metadata_from_bytes(&sg_file.to_bytes(), String::new(), String::new()).unwrap();
+        prop_assert_eq!(&loaded.raw_images[1], &sg_file.raw_images[1]);
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::*;
+use sg_image_reader::SgFileMetadata;
+
+#[test]
+fn gathers_bitmap_stats() {
+    let sprite = sprite_image(4, 3);
+    let images = [plain_image(3, 2), sprite.clone(), mirrored_image(&sprite, 1), with_alpha_mask(sprite_image(5, 5))];
+    let fixture = write_fixture("stats", 0xd6, &images);
+    let sg_file = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap();
+
+    let stats = sg_file.stats();
+
+    assert_eq!(stats.bitmaps.len(), 1);
+    assert_eq!(stats.bitmaps[0].images, stats.total);
+    assert_eq!(stats.total.image_count, 4);
+    assert_eq!(stats.total.images_by_type.get(&0), Some(&1));
+    assert_eq!(stats.total.images_by_type.get(&256), Some(&3));
+    assert_eq!(stats.total.mirrored_count, 1);
+    assert_eq!(stats.total.total_width, 3 + 4 + 4 + 5);
+    assert_eq!(stats.total.rgba_bytes, (6 + 12 + 12 + 25) * 4);
+    assert_eq!(stats.total.alpha_bytes, images[3].alpha.len() as u64);
+    assert_eq!(stats.total.internal_bytes, sg_file.file_size_555 as u64);
+    assert_eq!(stats.total.external_bytes, 0);
+}
+
+#[test]
+fn renders_stats() {
+    let fixture = write_fixture("rendered_stats", 0xd5, &[plain_image(3, 2), sprite_image(4, 4)]);
+    let stats = SgFileMetadata::load_metadata_from_path(&fixture.sg_path).unwrap().stats();
+
+    let text = stats.to_text();
+    assert!(text.lines().nth(2).unwrap().contains("rendered_stats.bmp"));
+    assert!(text.lines().nth(3).unwrap().trim_start().starts_with("total"));
+
+    let csv = stats.to_csv();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.lines().nth(1).unwrap().starts_with("0,rendered_stats.bmp,2,0,3.50,3.00,"));
+    assert!(csv.lines().nth(1).unwrap().ends_with(",0:1;256:1"));
+
+    let json = stats.to_json();
+    assert!(json.starts_with("{\"filename\":\"rendered_stats.sg3\",\"bitmaps\":[{\"bitmap\":0,\"name\":\"rendered_stats.bmp\",\"images\":{\"count\":2,\"by_type\":{\"0\":1,\"256\":1}"));
+}